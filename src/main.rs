@@ -1,30 +1,102 @@
 extern crate cgmath;
+extern crate jpeg_decoder;
+extern crate png;
 extern crate scoped_threadpool;
 extern crate sdl2;
+extern crate serde;
+extern crate toml;
 
 mod tracer;
 
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::mouse::MouseButton;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+use std::sync::Arc;
+
 use cgmath::Vector3;
 
-use std::sync::Arc;
+use tracer::loader::{load_scene, LoadedScene};
+use tracer::types::Surface;
 
-use tracer::types::{Camera, Color, Light, Material, Scene, Surface};
+// Fly-through controls
+const MOVE_SPEED: f32 = 10.0; // units per second
+const FAST_MULTIPLIER: f32 = 4.0;
+const SLOW_MULTIPLIER: f32 = 0.25;
+const MOUSE_SENSITIVITY: f32 = 0.15; // degrees per pixel
+const ZOOM_STEP: f32 = 0.9;
 
-use tracer::traceable::shapes;
+struct Options {
+    scene: PathBuf,
+    // Render a single frame to this file instead of opening a window
+    output: Option<PathBuf>,
+}
 
-const WINDOW_WIDTH: u32 = 800;
-const WINDOW_HEIGHT: u32 = 600;
+fn parse_args() -> Result<Options, String> {
+    let mut scene = None;
+    let mut output = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| format!("{} expects a file path", arg))?;
+                output = Some(PathBuf::from(path));
+            }
+            _ if arg.starts_with('-') => return Err(format!("Unknown argument '{}'", arg)),
+            _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+    Ok(Options {
+        scene: scene.ok_or_else(|| String::from("No scene file given"))?,
+        output,
+    })
+}
+
+fn render_to_file(loaded: LoadedScene, path: &Path) -> Result<(), String> {
+    let LoadedScene {
+        scene,
+        mut camera,
+        width,
+        height,
+        tone_map,
+        exposure,
+    } = loaded;
+    let surface = Arc::new(Surface::new(width, height));
+    {
+        let mut pixels = surface.pixels.lock().unwrap();
+        pixels.tone_map = tone_map;
+        pixels.exposure = exposure;
+    }
+    camera.set_surface(Arc::clone(&surface));
+
+    let start: Instant = Instant::now();
+    camera.render_scene(&scene)?;
+    println!("Rendered in {:.3}s", start.elapsed().as_secs_f32());
+
+    let pixels = surface.pixels.lock().unwrap();
+    pixels.save(path)
+}
+
+fn run_viewer(loaded: LoadedScene) {
+    let LoadedScene {
+        scene,
+        mut camera,
+        width,
+        height,
+        tone_map,
+        exposure,
+    } = loaded;
 
-fn main() {
     let sdl = sdl2::init().unwrap();
     let video_subsystem = sdl.video().unwrap();
 
     let window = video_subsystem
-        .window("rustracer", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window("rustracer", width as u32, height as u32)
         .position_centered()
         .build()
         .unwrap();
@@ -39,66 +111,26 @@ fn main() {
         .create_texture(
             texture_creator.default_pixel_format(),
             sdl2::render::TextureAccess::Static,
-            WINDOW_WIDTH,
-            WINDOW_HEIGHT,
+            width as u32,
+            height as u32,
         )
         .unwrap();
 
-    let surface = Arc::new(Surface::new(WINDOW_WIDTH as usize, WINDOW_HEIGHT as usize));
-    let mut camera = Camera::new(Vector3::new(0.0, 0.0, 0.0), 120_f32);
+    let surface = Arc::new(Surface::new(width, height));
+    {
+        let mut pixels = surface.pixels.lock().unwrap();
+        pixels.tone_map = tone_map;
+        pixels.exposure = exposure;
+    }
     camera.set_surface(Arc::clone(&surface));
+    camera.set_progressive(true);
 
     let mut event_pump = sdl.event_pump().unwrap();
     let mut i = 0;
+    let mut frame_time: f32 = 0.0;
 
-    let mut scene: Scene = Scene::new();
-
-    let ivory: Material = Material::new(
-        Color::RGB(
-            (0.3 * 255.0) as u8,
-            (0.2 * 255.0) as u8,
-            (0.4 * 255.0) as u8,
-        ),
-        0.6,
-        0.3,
-        50.0,
-        0.3,
-        0.0,
-        1.0,
-    );
-    let rubber: Material = Material::new(
-        Color::RGB(
-            (0.3 * 255.0) as u8,
-            (0.1 * 255.0) as u8,
-            (0.3 * 255.0) as u8,
-        ),
-        0.9,
-        0.1,
-        10.0,
-        0.1,
-        0.0,
-        1.0,
-    );
-    let mirror: Material = Material::new(Color::RGB(200, 0, 150), 0.1, 0.0, 1000.0, 0.9, 0.0, 1.0);
-    let glass: Material = Material::new(Color::RGB(150, 10, 50), 0.1, 0.2, 100.0, 0.2, 0.7, 1.5);
-
-    #[rustfmt::skip]
-    scene
-        .add_object(Box::new(shapes::Sphere::new( Vector3::new(-1.0, -1.5, -22.0), 2.0, ivory)))
-        .add_object(Box::new(shapes::Sphere::new( Vector3::new(1.5, -0.5, -25.0), 2.0, rubber)))
-        // .add_object(Box::new(Sphere::new( Vector3::new(1.5, -0.5, -25.0), 2000.0, rubber)))
-        .add_object(Box::new(shapes::Sphere::new( Vector3::new(-6.0, 4.5, -30.0), 4.0, mirror)))
-        .add_object(Box::new(shapes::Sphere::new( Vector3::new(2.0, 0.0, -15.0), 1.0, glass)))
-        .add_object(Box::new(shapes::Disk::new(  Vector3::new(0.0, 15.0, -30.0), Vector3::new(0.0, -1.0, 1.0), 10.0, mirror)))
-        // .add_object(Box::new(Plane::new(  Vector3::new(0.0, 15.0, -100.0), Vector3::new(0.0, -1.0, 1.0), mirror)))
-
-        .add_object(Box::new(shapes::Cube::new( Vector3::new(10.0, -5.0, -9.0), Vector3::new(5.0, 5.0, -24.0), glass)))
-
-        .add_light(Box::new(Light::new(Vector3::new(30.0, 50.0, -25.0), 1.8)))
-        .add_light(Box::new(Light::new(Vector3::new(-20.0, 20.0, 20.0), 1.5)))
-        .add_light(Box::new(Light::new(Vector3::new(30.0, 20.0, 30.0), 1.7)))
-        ;
-
+    // WASD to move, Q/E down/up, Shift/Ctrl to go faster/slower, hold the
+    // right mouse button to look around and scroll to zoom
     'windowpoll: loop {
         let start: Instant = Instant::now();
         i = (i + 1) % 255;
@@ -112,23 +144,85 @@ fn main() {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => break 'windowpoll,
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Right,
+                    ..
+                } => sdl.mouse().set_relative_mouse_mode(true),
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Right,
+                    ..
+                } => sdl.mouse().set_relative_mouse_mode(false),
+                Event::MouseMotion { xrel, yrel, .. } if sdl.mouse().relative_mouse_mode() => {
+                    camera.turn(xrel as f32 * MOUSE_SENSITIVITY, -yrel as f32 * MOUSE_SENSITIVITY);
+                }
+                Event::MouseWheel { y, .. } if y != 0 => camera.zoom(ZOOM_STEP.powi(y)),
                 _ => {}
             }
         }
 
+        let keys = event_pump.keyboard_state();
+        let axis = |positive: Scancode, negative: Scancode| {
+            keys.is_scancode_pressed(positive) as i32 as f32 - keys.is_scancode_pressed(negative) as i32 as f32
+        };
+        let direction = Vector3::new(
+            axis(Scancode::D, Scancode::A),
+            axis(Scancode::E, Scancode::Q),
+            axis(Scancode::W, Scancode::S),
+        );
+        if direction != Vector3::new(0.0, 0.0, 0.0) {
+            let mut speed = MOVE_SPEED;
+            if keys.is_scancode_pressed(Scancode::LShift) {
+                speed *= FAST_MULTIPLIER;
+            }
+            if keys.is_scancode_pressed(Scancode::LCtrl) {
+                speed *= SLOW_MULTIPLIER;
+            }
+            camera.translate_local(direction * speed * frame_time);
+        }
+
         camera.render_scene(&scene).expect("Couldn't render scene");
 
         texture
             .update(
                 None,
                 surface.pixels.lock().unwrap().pixels.as_slice(),
-                WINDOW_WIDTH as usize * 4,
+                width * 4,
             )
             .unwrap();
         canvas.copy(&texture, None, None).unwrap();
 
         canvas.present();
         let duration: Duration = start.elapsed();
-        println!("FPS: {}", 1.0 / duration.as_secs_f32());
+        frame_time = duration.as_secs_f32();
+        println!("FPS: {} ({} frames accumulated)", 1.0 / frame_time, camera.accumulated_frames());
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("Usage: rustracer <scene.toml> [--output <image.png|image.ppm>]");
+            std::process::exit(2);
+        }
+    };
+
+    let loaded = match load_scene(&options.scene) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    match options.output {
+        Some(path) => {
+            if let Err(err) = render_to_file(loaded, &path) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        None => run_viewer(loaded),
     }
 }
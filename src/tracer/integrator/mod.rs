@@ -0,0 +1,36 @@
+mod path;
+mod whitted;
+
+pub use path::PathTracer;
+pub use whitted::Whitted;
+
+use cgmath::Vector3;
+
+use super::light::{Illumine, LightSample};
+use super::ops;
+use super::sampling::{Rng, SamplePattern};
+use super::traceable::Traceable;
+use super::types::{Radiance, Ray, Scene};
+
+// Estimates the radiance arriving at the camera along a primary ray
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, ray: &Ray, scene: &Scene, rng: &mut Rng) -> Radiance;
+}
+
+// Shadow ray targets on `light`, stratified over its surface
+fn light_samples(light: &dyn Illumine, at: Vector3<f32>, rng: &mut Rng) -> Vec<LightSample> {
+    SamplePattern::Stratified
+        .generate(light.sample_count(), rng)
+        .into_iter()
+        .map(|u| light.sample(at, u))
+        .collect()
+}
+
+// Whether the light sample can be seen from `point` on a surface facing `normal`
+fn unoccluded(scene: &Scene, point: Vector3<f32>, normal: Vector3<f32>, sample: &LightSample) -> bool {
+    let shadow_ray = Ray::new(ops::offset_origin(point, normal, sample.direction), sample.direction).unwrap();
+    match scene.ray_intersect(&shadow_ray) {
+        Some(shadow_hit) => shadow_hit.distance >= sample.distance,
+        None => true,
+    }
+}
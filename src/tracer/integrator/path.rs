@@ -0,0 +1,168 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+use super::{light_samples, unoccluded, Integrator};
+use crate::tracer::ops;
+use crate::tracer::sampling::{cosine_hemisphere, phong_lobe, Rng};
+use crate::tracer::traceable::Traceable;
+use crate::tracer::types::{Material, MaterialModel, Radiance, Ray, RayHit, Scene};
+
+// Unidirectional path tracer. Every bounce picks one of the material's lobes
+// (diffuse, Phong specular, mirror, refraction) in proportion to its weight,
+// or importance samples the metallic-roughness BRDF. Lights, emissive
+// objects included, are sampled directly at every diffuse or glossy vertex,
+// so emission found by bouncing only counts after mirrors and refraction.
+pub struct PathTracer {
+    max_depth: u32,
+    // Bounces before Russian roulette starts terminating paths
+    roulette_depth: u32,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            roulette_depth: 3,
+        }
+    }
+
+    // The material's BRDF, `view` points into the surface
+    fn brdf(material: &Material, normal: Vector3<f32>, view: Vector3<f32>, light_dir: Vector3<f32>) -> Radiance {
+        if let MaterialModel::MetallicRoughness(model) = material.model {
+            return model.eval(material.base_color, normal, -view, light_dir);
+        }
+        // Diffuse plus normalized Phong
+        let diffuse = material.base_color * (material.diffuse_reflection / PI);
+        let cos_alpha = ops::reflect(view, normal).dot(light_dir).max(0.0);
+        let exponent = material.specular_exp;
+        let specular = material.specular_reflection * (exponent + 2.0) / (2.0 * PI) * cos_alpha.powf(exponent);
+        diffuse + Radiance::gray(specular)
+    }
+
+    // Next-event estimation over the lights. Intensities follow the
+    // Whitted convention: a white diffuse surface facing a light of intensity
+    // 1 reflects a radiance of 1.
+    fn sample_lights(&self, hit: &RayHit, material: &Material, normal: Vector3<f32>, view: Vector3<f32>, scene: &Scene, rng: &mut Rng) -> Radiance {
+        let mut radiance = Radiance::black();
+        for light in scene.lights() {
+            let samples = light_samples(light.as_ref(), hit.hit, rng);
+            let sample_weight = 1.0 / samples.len() as f32;
+            for sample in samples.iter() {
+                let cos_theta = sample.direction.dot(normal);
+                if !(cos_theta > 0.0 && unoccluded(scene, hit.hit, hit.normal, sample)) {
+                    continue;
+                }
+                let irradiance = sample.intensity * (PI * sample_weight * cos_theta);
+                radiance += Self::brdf(material, normal, view, sample.direction) * irradiance;
+            }
+        }
+        radiance
+    }
+
+    // Picks one of the Phong material's lobes in proportion to its weight and
+    // a direction within it. Returns the direction, its throughput weight and
+    // whether the lobe is a perfect mirror or refraction. `normal` faces the
+    // incoming ray while `shading_normal` faces out.
+    fn sample_phong(material: &Material, normal: Vector3<f32>, shading_normal: Vector3<f32>, view: Vector3<f32>, rng: &mut Rng) -> Option<(Vector3<f32>, Radiance, bool)> {
+        let diffuse = material.diffuse_reflection;
+        let specular = material.specular_reflection;
+        let reflective = material.reflectiveness;
+        let total = diffuse + specular + reflective + material.refractiveness;
+        if total <= 0.0 {
+            return None;
+        }
+
+        // The chosen lobe is divided by its selection probability, which
+        // leaves `total` as the common factor
+        let pick = rng.next_f32() * total;
+        let (direction, weight, delta) = if pick < diffuse {
+            // Cosine-weighted, the BRDF * cos / pdf ratio is the albedo
+            let local = cosine_hemisphere(rng.next_f32(), rng.next_f32());
+            (ops::to_world(local, normal), material.base_color, false)
+        } else if pick < diffuse + specular {
+            let exponent = material.specular_exp;
+            let local = phong_lobe(rng.next_f32(), rng.next_f32(), exponent);
+            let direction = ops::to_world(local, ops::reflect(view, normal));
+            let cos_theta = direction.dot(normal);
+            if cos_theta <= 0.0 {
+                return None;
+            }
+            (direction, Radiance::gray((exponent + 2.0) / (exponent + 1.0) * cos_theta), false)
+        } else if pick < diffuse + specular + reflective {
+            (ops::reflect(view, normal), Radiance::gray(1.0), true)
+        } else {
+            // Reflected or transmitted as often as Fresnel says, so the
+            // weight stays 1. Total internal reflection always reflects.
+            let fresnel = ops::fresnel(view, shading_normal, material.refractive_index);
+            let direction = match ops::refract(view, shading_normal, material.refractive_index) {
+                Some(direction) if rng.next_f32() >= fresnel => direction.normalize(),
+                _ => ops::reflect(view, normal),
+            };
+            (direction, Radiance::gray(1.0), true)
+        };
+        Some((direction, weight * total, delta))
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, ray: &Ray, scene: &Scene, rng: &mut Rng) -> Radiance {
+        let mut radiance = Radiance::black();
+        let mut throughput = Radiance::gray(1.0);
+        let mut ray = Ray::new(ray.origin, ray.direction).unwrap();
+        // Camera rays see emitters directly
+        let mut delta_bounce = true;
+
+        for depth in 0..self.max_depth {
+            let hit = match scene.ray_intersect(&ray) {
+                Some(hit) => hit,
+                None => {
+                    radiance += throughput * scene.background();
+                    break;
+                }
+            };
+            let material = hit.material.resolve(hit.uv, hit.hit);
+            let view = ray.direction;
+            // Leaving through the back of a surface means the segment ran
+            // through the object's interior
+            if hit.normal.dot(view) > 0.0 {
+                throughput *= material.transmittance(hit.distance);
+            } else if delta_bounce {
+                radiance += throughput * material.emission;
+            }
+            // Diffuse and glossy lobes live on the side the ray came from
+            let shading_normal = hit.material.shading_normal(&hit);
+            let normal = if hit.normal.dot(view) > 0.0 { -shading_normal } else { shading_normal };
+
+            radiance += throughput * self.sample_lights(&hit, &material, normal, view, scene, rng);
+
+            let (direction, weight, delta) = match material.model {
+                MaterialModel::MetallicRoughness(model) => {
+                    let (pick, u, v) = (rng.next_f32(), rng.next_f32(), rng.next_f32());
+                    match model.sample(material.base_color, normal, -view, pick, u, v) {
+                        Some(sample) => (sample.direction, sample.weight, false),
+                        None => break,
+                    }
+                }
+                MaterialModel::Phong => match Self::sample_phong(&material, normal, shading_normal, view, rng) {
+                    Some(lobe) => lobe,
+                    None => break,
+                },
+            };
+            throughput *= weight;
+            delta_bounce = delta;
+
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.max_component().min(0.95);
+                if rng.next_f32() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+
+            ray = Ray::new(ops::offset_origin(hit.hit, hit.normal, direction), direction).unwrap();
+        }
+        radiance
+    }
+}
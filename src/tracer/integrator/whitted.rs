@@ -0,0 +1,182 @@
+use cgmath::dot;
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+use super::{light_samples, unoccluded, Integrator};
+use crate::tracer::ops;
+use crate::tracer::sampling::Rng;
+use crate::tracer::traceable::Traceable;
+use crate::tracer::types::{MaterialModel, MetallicRoughness, Radiance, Ray, RayHit, Scene};
+
+// Phong shading from point lights plus perfect mirror reflection and
+// Fresnel-weighted refraction, recursing up to `max_depth` bounces. Metallic-roughness
+// materials get their BRDF from the lights and a Fresnel-weighted mirror
+// reflection, since Whitted can't follow glossy bounces.
+pub struct Whitted {
+    max_depth: u32,
+}
+
+impl Whitted {
+    pub fn new(max_depth: u32) -> Self {
+        Self { max_depth }
+    }
+
+    fn trace(&self, ray: &Ray, scene: &Scene, rng: &mut Rng, depth: u32) -> Radiance {
+        if depth > 0 {
+            if let Some(hit_data) = scene.ray_intersect(ray) {
+                let material = hit_data.material.resolve(hit_data.uv, hit_data.hit);
+                // Shading follows the mapped normal, ray origins are still
+                // pushed off the real surface
+                let normal = hit_data.material.shading_normal(&hit_data);
+                // Rays leaving through the back of a surface crossed the
+                // object's interior to get there
+                // Emitters only glow from the front
+                let (transmittance, emitted) = if dot(ray.direction, hit_data.normal) > 0_f32 {
+                    (material.transmittance(hit_data.distance), Radiance::black())
+                } else {
+                    (Radiance::gray(1.0), material.emission)
+                };
+                if let MaterialModel::MetallicRoughness(model) = material.model {
+                    let reflected = self.shade_metallic_roughness(&model, material.base_color, &hit_data, normal, ray, scene, rng, depth);
+                    return (reflected + emitted) * transmittance;
+                }
+                let view_v3 = ray.direction;
+                let mut diffuse_light_intensity = Radiance::black();
+                let mut specular_light_intensity = Radiance::black();
+                let view_reflect_v3 = ops::reflect(view_v3, normal);
+                let reflect_orig: Vector3<f32> =
+                    if dot(view_reflect_v3, hit_data.normal) < 0_f32 {
+                        hit_data.hit - hit_data.normal * 1e-3
+                    } else {
+                        hit_data.hit + hit_data.normal * 1e-3
+                    };
+
+                // The transmitted share of light follows Fresnel, whatever
+                // it reflects joins the mirror reflection. Total internal
+                // reflection sends all of it there.
+                let fresnel = if material.refractiveness > 0.0 {
+                    ops::fresnel(view_v3, normal, material.refractive_index)
+                } else {
+                    0.0
+                };
+                let reflect_weight = material.reflectiveness + material.refractiveness * fresnel;
+                let refract_weight = material.refractiveness * (1.0 - fresnel);
+
+                let refract_color = match ops::refract(view_v3, normal, material.refractive_index) {
+                    Some(refract_dir) if refract_weight > 0.0 => {
+                        let refract_dir = refract_dir.normalize();
+                        let refract_orig = if refract_dir.dot(hit_data.normal) < 0_f32 {
+                            hit_data.hit - hit_data.normal * 1e-3
+                        } else {
+                            hit_data.hit + hit_data.normal * 1e-3
+                        };
+                        self.trace(
+                            &Ray::new(refract_orig, refract_dir).unwrap(),
+                            scene,
+                            rng,
+                            depth - 1,
+                        )
+                    }
+                    _ => Radiance::black(),
+                };
+
+                let reflect_color = self.trace(
+                    &Ray::new(reflect_orig, view_reflect_v3).unwrap(),
+                    scene,
+                    rng,
+                    depth - 1,
+                );
+                for light in scene.lights() {
+                    // Area lights average several shadow rays into a penumbra
+                    let samples = light_samples(light.as_ref(), hit_data.hit, rng);
+                    let sample_weight = 1.0 / samples.len() as f32;
+                    for sample in samples.iter() {
+                        let light_dir = sample.direction;
+                        let light_dot_norm = light_dir.dot(normal);
+
+                        // Shadows
+                        if !unoccluded(scene, hit_data.hit, hit_data.normal, sample) {
+                            continue;
+                        }
+
+                        let light_reflect_v3 = ops::reflect(light_dir, normal);
+                        let intensity = sample.intensity * sample_weight;
+
+                        diffuse_light_intensity += intensity * (0.0_f32).max(light_dot_norm);
+                        specular_light_intensity += intensity
+                            * light_reflect_v3
+                                .dot(view_v3)
+                                .max(0.0_f32)
+                                .powf(material.specular_exp);
+                    }
+                }
+                // diffuse part
+                let mut final_color = material.base_color
+                    * diffuse_light_intensity
+                    * material.diffuse_reflection;
+
+                // specular part, tinted by the lights
+                final_color += specular_light_intensity * material.specular_reflection;
+
+                // reflective part
+                final_color += reflect_color * reflect_weight;
+
+                // refractive part
+                final_color += refract_color * refract_weight;
+
+                // emitted part
+                final_color += emitted;
+
+                return final_color * transmittance;
+            }
+        }
+        scene.background()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn shade_metallic_roughness(
+        &self,
+        model: &MetallicRoughness,
+        base_color: Radiance,
+        hit: &RayHit,
+        normal: Vector3<f32>,
+        ray: &Ray,
+        scene: &Scene,
+        rng: &mut Rng,
+        depth: u32,
+    ) -> Radiance {
+        let wo = -ray.direction;
+        let normal = if hit.normal.dot(wo) < 0.0 { -normal } else { normal };
+        let mut radiance = Radiance::black();
+        for light in scene.lights() {
+            let samples = light_samples(light.as_ref(), hit.hit, rng);
+            let sample_weight = 1.0 / samples.len() as f32;
+            for sample in samples.iter() {
+                let cos_theta = sample.direction.dot(normal);
+                if !(cos_theta > 0.0 && unoccluded(scene, hit.hit, hit.normal, sample)) {
+                    continue;
+                }
+                // Same convention as the Phong lights: a white Lambertian
+                // surface facing a light of intensity 1 reflects 1
+                let irradiance = sample.intensity * (PI * sample_weight * cos_theta);
+                radiance += model.eval(base_color, normal, wo, sample.direction) * irradiance;
+            }
+        }
+
+        let reflectance = model.mirror_reflectance(base_color, normal.dot(wo));
+        if reflectance.max_component() > 1e-3 {
+            let direction = ops::reflect(ray.direction, normal);
+            let origin = ops::offset_origin(hit.hit, hit.normal, direction);
+            radiance += self.trace(&Ray::new(origin, direction).unwrap(), scene, rng, depth - 1) * reflectance;
+        }
+        radiance
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, ray: &Ray, scene: &Scene, rng: &mut Rng) -> Radiance {
+        self.trace(ray, scene, rng, self.max_depth)
+    }
+}
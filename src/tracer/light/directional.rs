@@ -0,0 +1,41 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use super::{Illumine, LightSample};
+use crate::tracer::types::Radiance;
+
+// Infinitely far away light like the sun, `direction` is the way the light
+// travels
+pub struct DirectionalLight {
+    direction: Vector3<f32>,
+    intensity: f32,
+    color: Radiance,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector3<f32>, intensity: f32) -> Result<Self, String> {
+        if direction.magnitude2() < 1e-12 {
+            return Err(String::from("Directional light direction must not be zero"));
+        }
+        Ok(Self {
+            direction: direction.normalize(),
+            intensity,
+            color: Radiance::gray(1.0),
+        })
+    }
+
+    pub fn set_color(&mut self, color: Radiance) {
+        self.color = color;
+    }
+}
+
+impl Illumine for DirectionalLight {
+    fn sample(&self, _at: Vector3<f32>, _u: (f32, f32)) -> LightSample {
+        LightSample {
+            direction: -self.direction,
+            // Anything along the way casts a shadow
+            distance: f32::INFINITY,
+            intensity: self.color * self.intensity,
+        }
+    }
+}
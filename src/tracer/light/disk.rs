@@ -0,0 +1,67 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+use super::{Attenuation, Illumine, LightSample};
+use crate::tracer::ops;
+use crate::tracer::sampling::concentric_disk;
+use crate::tracer::types::Radiance;
+
+// Emits from the side `normal` points to, falling off with the cosine like a
+// diffuse surface
+pub struct DiskLight {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    radius: f32,
+    intensity: f32,
+    color: Radiance,
+    attenuation: Attenuation,
+    samples: u32,
+}
+
+impl DiskLight {
+    pub fn new(position: Vector3<f32>, normal: Vector3<f32>, radius: f32, intensity: f32, samples: u32) -> Result<Self, String> {
+        if normal.magnitude2() < 1e-12 {
+            return Err(String::from("Disk light normal must not be zero"));
+        }
+        Ok(Self {
+            position,
+            normal: normal.normalize(),
+            radius,
+            intensity,
+            color: Radiance::gray(1.0),
+            attenuation: Attenuation::None,
+            samples: samples.max(1),
+        })
+    }
+
+    pub fn set_color(&mut self, color: Radiance) {
+        self.color = color;
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+}
+
+impl Illumine for DiskLight {
+    fn sample(&self, at: Vector3<f32>, u: (f32, f32)) -> LightSample {
+        let (x, y) = concentric_disk(u.0, u.1);
+        let point = self.position + ops::to_world(Vector3::new(x, y, 0.0), self.normal) * self.radius;
+        let to_light = point - at;
+        let distance = to_light.magnitude();
+        let direction = to_light / distance;
+        let area = PI * self.radius * self.radius;
+        let cos_light = (-direction.dot(self.normal)).max(0.0);
+        LightSample {
+            direction,
+            distance,
+            intensity: self.color * (self.intensity * cos_light * self.attenuation.area_factor(distance, area)),
+        }
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.samples
+    }
+}
@@ -0,0 +1,103 @@
+use cgmath::prelude::*;
+use cgmath::{Matrix3, Matrix4, Vector3, Vector4};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use super::{Illumine, LightSample};
+use crate::tracer::traceable::Sampleable;
+use crate::tracer::types::{Material, Radiance};
+
+// An emissive object in the scene, lit by its material's emission. The
+// shape is shared with the scene, or for loose triangles is a mesh of the
+// same triangles, so the two always agree.
+pub struct EmissiveLight {
+    shape: Arc<dyn Sampleable>,
+    material: Material,
+    // Where the scene places the shape, points are sampled in its local space
+    transform: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+    normal_transform: Matrix3<f32>,
+    // Growth of a unit of area between local and world space, before the
+    // dependence on the normal
+    determinant: f32,
+    samples: u32,
+}
+
+impl EmissiveLight {
+    pub fn new(shape: Arc<dyn Sampleable>, material: Material, samples: u32) -> Self {
+        Self {
+            shape,
+            material,
+            transform: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            normal_transform: Matrix3::identity(),
+            determinant: 1.0,
+            samples: samples.max(1),
+        }
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4<f32>) -> Result<(), String> {
+        let inverse = transform
+            .invert()
+            .ok_or_else(|| String::from("Transform is not invertible"))?;
+        let linear = Matrix3::from_cols(transform.x.truncate(), transform.y.truncate(), transform.z.truncate());
+        self.transform = transform;
+        self.inverse = inverse;
+        self.normal_transform = Matrix3::from_cols(inverse.x.truncate(), inverse.y.truncate(), inverse.z.truncate()).transpose();
+        self.determinant = linear.determinant().abs();
+        Ok(())
+    }
+}
+
+impl Illumine for EmissiveLight {
+    fn sample(&self, at: Vector3<f32>, u: (f32, f32)) -> LightSample {
+        let local_at = (self.inverse * Vector4::new(at.x, at.y, at.z, 1.0)).truncate();
+        let sample = self.shape.sample_surface_from(local_at, u);
+        let point = (self.transform * Vector4::new(sample.point.x, sample.point.y, sample.point.z, 1.0)).truncate();
+        // Areas stretch by det(M) |M^-T n|, which spreads the density thinner
+        let normal = self.normal_transform * sample.normal;
+        let stretch = self.determinant * normal.magnitude();
+        let normal = normal.normalize();
+
+        let to_light = point - at;
+        let distance = to_light.magnitude();
+        // A shading point on the emitter itself gets nothing from right
+        // where it sits, and there is no direction to divide out
+        if distance <= 1e-4 {
+            return LightSample {
+                direction: normal,
+                distance: 0.0,
+                intensity: Radiance::black(),
+            };
+        }
+        let direction = to_light / distance;
+        let cos_light = -direction.dot(normal);
+        if !(sample.pdf > 0.0 && cos_light > 0.0) {
+            return LightSample {
+                direction,
+                distance: 0.0,
+                intensity: Radiance::black(),
+            };
+        }
+
+        // Irradiance from the sampled patch is L cos_light cos / (d^2 pdf),
+        // the integrators multiply by pi cos
+        let pdf = sample.pdf / stretch;
+        let emitted = self.material.emission_at(sample.uv, point);
+        // Aim the shadow ray just off the emitter. The 1e-3 its origin gets
+        // pushed sideways would clip the emitter early at grazing angles if
+        // it ended on the surface.
+        let target = point + normal * 2e-3 - at;
+        let target_distance = target.magnitude();
+        LightSample {
+            direction: target / target_distance,
+            distance: target_distance,
+            intensity: emitted * (cos_light / (PI * distance * distance * pdf)),
+        }
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.samples
+    }
+}
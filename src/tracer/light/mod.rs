@@ -0,0 +1,76 @@
+mod point;
+mod spot;
+mod directional;
+mod rect;
+mod disk;
+mod sphere;
+mod emissive;
+
+pub use point::PointLight;
+pub use spot::SpotLight;
+pub use directional::DirectionalLight;
+pub use rect::RectLight;
+pub use disk::DiskLight;
+pub use sphere::SphereLight;
+pub use emissive::EmissiveLight;
+
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+use crate::tracer::types::Radiance;
+
+// A point on a light as seen from a shading point
+pub struct LightSample {
+    // Unit vector from the shading point towards the light
+    pub direction: Vector3<f32>,
+    // Shadow rays only need to reach this far
+    pub distance: f32,
+    // Already tinted by the light's color
+    pub intensity: Radiance,
+}
+
+pub trait Illumine {
+    // `u` is a point in [0, 1)^2 used to pick a point on the light
+    fn sample(&self, at: Vector3<f32>, u: (f32, f32)) -> LightSample;
+
+    // Shadow rays per shading point, more give smoother penumbrae
+    fn sample_count(&self) -> u32 {
+        1
+    }
+}
+
+// How the intensity of a light placed in the scene falls off with distance
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Attenuation {
+    // Same intensity at any distance
+    #[default]
+    None,
+    InverseSquare,
+    // 1 / (constant + linear * d + quadratic * d^2)
+    Custom { constant: f32, linear: f32, quadratic: f32 },
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f32) -> f32 {
+        match *self {
+            Attenuation::None => 1.0,
+            Attenuation::InverseSquare => 1.0 / (distance * distance).max(1e-6),
+            Attenuation::Custom { constant, linear, quadratic } => {
+                1.0 / (constant + linear * distance + quadratic * distance * distance).max(1e-6)
+            }
+        }
+    }
+
+    // Scales a sample of an area light, `area` being what the light looks
+    // like from `distance` away. Unattenuated area lights light a facing
+    // surface with their intensity, whatever their size or distance. Once
+    // attenuated, the intensity is the radiance leaving the light, so with
+    // `InverseSquare` it matches an emissive surface of the same shape.
+    pub fn area_factor(&self, distance: f32, area: f32) -> f32 {
+        match self {
+            Attenuation::None => 1.0,
+            _ => self.factor(distance) * area / PI,
+        }
+    }
+}
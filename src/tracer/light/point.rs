@@ -0,0 +1,43 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use super::{Attenuation, Illumine, LightSample};
+use crate::tracer::types::Radiance;
+
+pub struct PointLight {
+    position: Vector3<f32>,
+    intensity: f32,
+    color: Radiance,
+    attenuation: Attenuation,
+}
+
+impl PointLight {
+    pub fn new(position: Vector3<f32>, intensity: f32) -> Self {
+        Self {
+            position,
+            intensity,
+            color: Radiance::gray(1.0),
+            attenuation: Attenuation::None,
+        }
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    pub fn set_color(&mut self, color: Radiance) {
+        self.color = color;
+    }
+}
+
+impl Illumine for PointLight {
+    fn sample(&self, at: Vector3<f32>, _u: (f32, f32)) -> LightSample {
+        let to_light = self.position - at;
+        let distance = to_light.magnitude();
+        LightSample {
+            direction: to_light / distance,
+            distance,
+            intensity: self.color * (self.intensity * self.attenuation.factor(distance)),
+        }
+    }
+}
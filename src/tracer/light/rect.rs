@@ -0,0 +1,66 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use super::{Attenuation, Illumine, LightSample};
+use crate::tracer::types::Radiance;
+
+// Parallelogram spanned by `edge_u` and `edge_v` from `corner`. Emits from
+// the side `edge_u x edge_v` points to, falling off with the cosine like a
+// diffuse surface.
+pub struct RectLight {
+    corner: Vector3<f32>,
+    edge_u: Vector3<f32>,
+    edge_v: Vector3<f32>,
+    normal: Vector3<f32>,
+    intensity: f32,
+    color: Radiance,
+    attenuation: Attenuation,
+    samples: u32,
+}
+
+impl RectLight {
+    pub fn new(corner: Vector3<f32>, edge_u: Vector3<f32>, edge_v: Vector3<f32>, intensity: f32, samples: u32) -> Result<Self, String> {
+        let normal = edge_u.cross(edge_v);
+        if normal.magnitude2() < 1e-12 {
+            return Err(String::from("Rectangle light edges must not be parallel"));
+        }
+        Ok(Self {
+            corner,
+            edge_u,
+            edge_v,
+            normal: normal.normalize(),
+            intensity,
+            color: Radiance::gray(1.0),
+            attenuation: Attenuation::None,
+            samples: samples.max(1),
+        })
+    }
+
+    pub fn set_color(&mut self, color: Radiance) {
+        self.color = color;
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+}
+
+impl Illumine for RectLight {
+    fn sample(&self, at: Vector3<f32>, u: (f32, f32)) -> LightSample {
+        let point = self.corner + self.edge_u * u.0 + self.edge_v * u.1;
+        let to_light = point - at;
+        let distance = to_light.magnitude();
+        let direction = to_light / distance;
+        let area = self.edge_u.cross(self.edge_v).magnitude();
+        let cos_light = (-direction.dot(self.normal)).max(0.0);
+        LightSample {
+            direction,
+            distance,
+            intensity: self.color * (self.intensity * cos_light * self.attenuation.area_factor(distance, area)),
+        }
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.samples
+    }
+}
@@ -0,0 +1,75 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+use super::{Attenuation, Illumine, LightSample};
+use crate::tracer::ops;
+use crate::tracer::sampling::uniform_hemisphere;
+use crate::tracer::types::Radiance;
+
+// Emits evenly in every direction
+pub struct SphereLight {
+    center: Vector3<f32>,
+    radius: f32,
+    intensity: f32,
+    color: Radiance,
+    attenuation: Attenuation,
+    samples: u32,
+}
+
+impl SphereLight {
+    pub fn new(center: Vector3<f32>, radius: f32, intensity: f32, samples: u32) -> Self {
+        Self {
+            center,
+            radius,
+            intensity,
+            color: Radiance::gray(1.0),
+            attenuation: Attenuation::None,
+            samples: samples.max(1),
+        }
+    }
+
+    pub fn set_color(&mut self, color: Radiance) {
+        self.color = color;
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+}
+
+impl Illumine for SphereLight {
+    fn sample(&self, at: Vector3<f32>, u: (f32, f32)) -> LightSample {
+        let to_center = self.center - at;
+        // Inside the light there is no surface to shade against
+        if to_center.magnitude() <= self.radius {
+            return LightSample {
+                direction: to_center.normalize(),
+                distance: 0.0,
+                intensity: self.color * self.intensity,
+            };
+        }
+
+        // Only the hemisphere facing `at` can be seen. Weighting it by the
+        // cosine makes it look like a disk facing `at`, the 2 undoes the
+        // average cosine over a hemisphere.
+        let surface_normal = ops::to_world(uniform_hemisphere(u.0, u.1), -to_center.normalize());
+        let point = self.center + surface_normal * self.radius;
+        let to_light = point - at;
+        let distance = to_light.magnitude();
+        let direction = to_light / distance;
+        // The disk it looks like
+        let area = PI * self.radius * self.radius;
+        let cos_light = (-direction.dot(surface_normal)).max(0.0);
+        LightSample {
+            direction,
+            distance,
+            intensity: self.color * (2.0 * self.intensity * cos_light * self.attenuation.area_factor(distance, area)),
+        }
+    }
+
+    fn sample_count(&self) -> u32 {
+        self.samples
+    }
+}
@@ -0,0 +1,71 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use super::{Attenuation, Illumine, LightSample};
+use crate::tracer::types::Radiance;
+
+// Point light restricted to a cone around `direction`. Full intensity inside
+// `inner_angle`, fading out smoothly towards `outer_angle` (both in degrees,
+// measured from the axis).
+pub struct SpotLight {
+    position: Vector3<f32>,
+    direction: Vector3<f32>,
+    cos_inner: f32,
+    cos_outer: f32,
+    intensity: f32,
+    color: Radiance,
+    attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(position: Vector3<f32>, direction: Vector3<f32>, inner_angle: f32, outer_angle: f32, intensity: f32) -> Result<Self, String> {
+        if direction.magnitude2() < 1e-12 {
+            return Err(String::from("Spot light direction must not be zero"));
+        }
+        if !(0.0..=180.0).contains(&outer_angle) || !(0.0..=outer_angle).contains(&inner_angle) {
+            return Err(String::from("Spot light angles must satisfy 0 <= inner_angle <= outer_angle <= 180"));
+        }
+        Ok(Self {
+            position,
+            direction: direction.normalize(),
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+            intensity,
+            color: Radiance::gray(1.0),
+            attenuation: Attenuation::None,
+        })
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    fn cone_falloff(&self, cos_angle: f32) -> f32 {
+        if cos_angle >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_angle <= self.cos_outer {
+            return 0.0;
+        }
+        let t = (cos_angle - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    pub fn set_color(&mut self, color: Radiance) {
+        self.color = color;
+    }
+}
+
+impl Illumine for SpotLight {
+    fn sample(&self, at: Vector3<f32>, _u: (f32, f32)) -> LightSample {
+        let to_light = self.position - at;
+        let distance = to_light.magnitude();
+        let direction = to_light / distance;
+        let falloff = self.cone_falloff(-direction.dot(self.direction));
+        LightSample {
+            direction,
+            distance,
+            intensity: self.color * (self.intensity * falloff * self.attenuation.factor(distance)),
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod obj;
+pub mod scene;
+
+pub use obj::load_obj;
+pub use scene::{load_scene, LoadedScene};
@@ -0,0 +1,241 @@
+use cgmath::{ Vector2, Vector3 };
+
+use std::fs::File;
+use std::io::{ BufRead, BufReader };
+use std::path::Path;
+
+use crate::tracer::material;
+use crate::tracer::traceable::shapes::{ MeshFace, MeshGroup, TriangleMesh };
+
+pub fn load_obj<P: AsRef<Path>>(path: P, material: material::Material) -> Result<TriangleMesh, String> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    parse_obj(BufReader::new(file), material).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+pub fn parse_obj<R: BufRead>(reader: R, material: material::Material) -> Result<TriangleMesh, String> {
+    let mut positions: Vec<Vector3<f32>> = Vec::new();
+    let mut normals: Vec<Vector3<f32>> = Vec::new();
+    let mut uvs: Vec<Vector2<f32>> = Vec::new();
+    let mut faces: Vec<MeshFace> = Vec::new();
+    let mut groups: Vec<MeshGroup> = Vec::new();
+    let mut group_name = String::from("default");
+    let mut group_start = 0;
+
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let line = line.map_err(|err| format!("line {}: {}", line_no, err))?;
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => &line[..],
+        };
+
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let v = parse_floats(&args, 3, line_no)?;
+                positions.push(Vector3::new(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let v = parse_floats(&args, 3, line_no)?;
+                normals.push(Vector3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                // v is optional and defaults to 0
+                let v = parse_floats(&args, args.len().clamp(1, 2), line_no)?;
+                uvs.push(Vector2::new(v[0], v.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(format!("line {}: face needs at least 3 vertices, got {}", line_no, args.len()));
+                }
+                let vertices = args
+                    .iter()
+                    .map(|arg| parse_face_vertex(arg, positions.len(), uvs.len(), normals.len(), line_no))
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                let has_uvs = vertices.iter().all(|v| v.1.is_some());
+                let has_normals = vertices.iter().all(|v| v.2.is_some());
+
+                // Triangulate polygons as a fan around the first vertex
+                for i in 1..vertices.len() - 1 {
+                    let tri = [vertices[0], vertices[i], vertices[i + 1]];
+                    faces.push(MeshFace {
+                        positions: [tri[0].0, tri[1].0, tri[2].0],
+                        uvs: if has_uvs {
+                            Some([tri[0].1.unwrap(), tri[1].1.unwrap(), tri[2].1.unwrap()])
+                        } else {
+                            None
+                        },
+                        normals: if has_normals {
+                            Some([tri[0].2.unwrap(), tri[1].2.unwrap(), tri[2].2.unwrap()])
+                        } else {
+                            None
+                        },
+                    });
+                }
+            }
+            "g" | "o" => {
+                if faces.len() > group_start {
+                    groups.push(MeshGroup {
+                        name: group_name,
+                        faces: group_start..faces.len(),
+                    });
+                }
+                group_name = if args.is_empty() { String::from("default") } else { args.join(" ") };
+                group_start = faces.len();
+            }
+            // Materials, smoothing groups and free-form geometry aren't supported
+            _ => {}
+        }
+    }
+
+    if faces.len() > group_start {
+        groups.push(MeshGroup {
+            name: group_name,
+            faces: group_start..faces.len(),
+        });
+    }
+
+    if faces.is_empty() {
+        return Err(String::from("no faces found"));
+    }
+
+    Ok(TriangleMesh::new(positions, normals, uvs, faces, groups, material))
+}
+
+fn parse_floats(args: &[&str], count: usize, line_no: usize) -> Result<Vec<f32>, String> {
+    // Extra components (w for positions and texture coordinates) are ignored
+    if args.len() < count {
+        return Err(format!("line {}: expected {} components, got {}", line_no, count, args.len()));
+    }
+    args[..count]
+        .iter()
+        .map(|arg| {
+            arg.parse::<f32>()
+                .map_err(|_| format!("line {}: invalid number '{}'", line_no, arg))
+        })
+        .collect()
+}
+
+fn parse_face_vertex(
+    arg: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+    line_no: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = arg.split('/');
+    let position = match parts.next() {
+        Some(idx) if !idx.is_empty() => resolve_index(idx, position_count, line_no)?,
+        _ => return Err(format!("line {}: missing vertex index in '{}'", line_no, arg)),
+    };
+    let uv = match parts.next() {
+        Some(idx) if !idx.is_empty() => Some(resolve_index(idx, uv_count, line_no)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(idx) if !idx.is_empty() => Some(resolve_index(idx, normal_count, line_no)?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(format!("line {}: malformed face vertex '{}'", line_no, arg));
+    }
+    Ok((position, uv, normal))
+}
+
+// OBJ indices are 1-based, negative ones count back from the last element
+fn resolve_index(idx: &str, count: usize, line_no: usize) -> Result<usize, String> {
+    let value = idx
+        .parse::<i64>()
+        .map_err(|_| format!("line {}: invalid index '{}'", line_no, idx))?;
+    let resolved = if value > 0 {
+        value - 1
+    } else {
+        count as i64 + value
+    };
+    if value == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("line {}: index {} out of range", line_no, value));
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<TriangleMesh, String> {
+        parse_obj(source.as_bytes(), material::Material::default())
+    }
+
+    fn parse_err(source: &str) -> String {
+        parse(source).err().expect("expected a parse error")
+    }
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    #[test]
+    fn negative_indices_count_back() {
+        let mesh = parse(&format!("{}f -3 -2 -1\n", TRIANGLE)).unwrap();
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+    }
+
+    #[test]
+    fn polygons_are_fanned() {
+        let mesh = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+        assert_eq!(mesh.faces.len(), 2);
+        assert_eq!(mesh.faces[1].positions, [0, 2, 3]);
+    }
+
+    #[test]
+    fn texture_coordinates_need_only_u() {
+        let mesh = parse(&format!("{}vt 0.5\nvt 0.25 0.75 0\nf 1/1 2/2 3/1\n", TRIANGLE)).unwrap();
+        assert_eq!(mesh.uvs, vec![Vector2::new(0.5, 0.0), Vector2::new(0.25, 0.75)]);
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        assert_eq!(parse_err(&format!("{}f 1 2 4\n", TRIANGLE)), "line 4: index 4 out of range");
+    }
+
+    #[test]
+    fn zero_index() {
+        assert_eq!(parse_err(&format!("{}f 0 1 2\n", TRIANGLE)), "line 4: index 0 out of range");
+    }
+
+    #[test]
+    fn negative_index_out_of_range() {
+        assert_eq!(parse_err(&format!("{}f -4 -2 -1\n", TRIANGLE)), "line 4: index -4 out of range");
+    }
+
+    #[test]
+    fn non_numeric_index() {
+        assert_eq!(parse_err(&format!("{}f 1 a 3\n", TRIANGLE)), "line 4: invalid index 'a'");
+    }
+
+    #[test]
+    fn short_face() {
+        assert_eq!(parse_err(&format!("{}f 1 2\n", TRIANGLE)), "line 4: face needs at least 3 vertices, got 2");
+    }
+
+    #[test]
+    fn short_vertex() {
+        assert_eq!(parse_err("v 0 0\n"), "line 1: expected 3 components, got 2");
+    }
+
+    #[test]
+    fn missing_texture_coordinate() {
+        assert_eq!(parse_err(&format!("{}vt\n", TRIANGLE)), "line 4: expected 1 components, got 0");
+    }
+
+    #[test]
+    fn no_faces() {
+        assert_eq!(parse_err(TRIANGLE), "no faces found");
+    }
+}
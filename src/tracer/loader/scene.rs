@@ -0,0 +1,1008 @@
+use cgmath::{ Deg, Matrix4, Vector3, Vector4 };
+use cgmath::prelude::*;
+use serde::Deserialize;
+use toml::Spanned;
+
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+
+use crate::tracer::material;
+use crate::tracer::texture::{ Checker, Gradient, ImageTexture, Marble, NoiseTexture, Perlin, Texture, TextureSpace, WrapMode, Wood };
+use crate::tracer::traceable::{ shapes, Sampleable, Traceable, Transformed };
+use crate::tracer::types::{ Camera, Filter, FovAxis, PathTracer, Projection, Radiance, SamplePattern, Scene, ToneMap, Whitted };
+use crate::tracer::types::{ Attenuation, DirectionalLight, DiskLight, PointLight, RectLight, SphereLight, SpotLight, EmissiveLight };
+
+pub struct LoadedScene {
+    pub scene: Scene,
+    pub camera: Camera,
+    pub width: usize,
+    pub height: usize,
+    pub tone_map: ToneMap,
+    pub exposure: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    #[serde(default)]
+    render: RenderDesc,
+    camera: CameraDesc,
+    #[serde(default)]
+    textures: HashMap<String, Spanned<TextureDesc>>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDesc>,
+    #[serde(default)]
+    sphere: Vec<SphereDesc>,
+    #[serde(default)]
+    plane: Vec<PlaneDesc>,
+    #[serde(default)]
+    disk: Vec<DiskDesc>,
+    #[serde(default)]
+    cube: Vec<CubeDesc>,
+    #[serde(default)]
+    triangle: Vec<TriangleDesc>,
+    #[serde(default)]
+    mesh: Vec<MeshDesc>,
+    #[serde(default)]
+    light: Vec<LightDesc>,
+    #[serde(default)]
+    spot_light: Vec<SpotLightDesc>,
+    #[serde(default)]
+    directional_light: Vec<DirectionalLightDesc>,
+    #[serde(default)]
+    rect_light: Vec<RectLightDesc>,
+    #[serde(default)]
+    disk_light: Vec<DiskLightDesc>,
+    #[serde(default)]
+    sphere_light: Vec<SphereLightDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RenderDesc {
+    // 800 x 600 by default
+    width: Option<Spanned<usize>>,
+    height: Option<Spanned<usize>>,
+    tone_map: ToneMapDesc,
+    // Only used by `extended_reinhard`
+    white: f32,
+    exposure: f32,
+    samples: u32,
+    sampler: SamplerDesc,
+    filter: FilterDesc,
+    integrator: IntegratorDesc,
+    // Bounces, defaults to 4 for `whitted` and 16 for `path`
+    max_depth: Option<u32>,
+    // Linear RGB seen by rays that leave the scene
+    background: Option<[f32; 3]>,
+}
+
+impl Default for RenderDesc {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            tone_map: ToneMapDesc::Clamp,
+            white: 4.0,
+            exposure: 0.0,
+            samples: 1,
+            sampler: SamplerDesc::Stratified,
+            filter: FilterDesc::Box,
+            integrator: IntegratorDesc::Whitted,
+            max_depth: None,
+            background: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ToneMapDesc {
+    Clamp,
+    Reinhard,
+    ExtendedReinhard,
+    Aces,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SamplerDesc {
+    Jittered,
+    Stratified,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum IntegratorDesc {
+    Whitted,
+    Path,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FilterDesc {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    #[serde(default)]
+    origin: [f32; 3],
+    // Looks down -Z when no target is given
+    target: Option<Spanned<[f32; 3]>>,
+    // +Y by default
+    up: Option<Spanned<[f32; 3]>>,
+    #[serde(default)]
+    roll: f32,
+    #[serde(default)]
+    projection: ProjectionDesc,
+    // Degrees, for perspective and fisheye projections
+    fov: Option<f32>,
+    #[serde(default)]
+    fov_axis: FovAxisDesc,
+    // World units, for orthographic projections
+    height: Option<f32>,
+    aspect: Option<f32>,
+    // Lens radius, leave out or at 0 for a pinhole camera
+    aperture: Option<Spanned<f32>>,
+    // Defaults to the distance to `target`
+    focus_distance: Option<Spanned<f32>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum ProjectionDesc {
+    #[default]
+    Perspective,
+    Orthographic,
+    Fisheye,
+    Equirectangular,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum FovAxisDesc {
+    Horizontal,
+    #[default]
+    Vertical,
+}
+
+// Colors are linear RGB, usually in [0, 1]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDesc {
+    #[serde(default = "white")]
+    color: [f32; 3],
+    // Names of textures multiplying `color` and the scalars below, scalars
+    // use the texture's luminance
+    albedo_map: Option<Spanned<String>>,
+    diffuse_map: Option<Spanned<String>>,
+    specular_map: Option<Spanned<String>>,
+    specular_exp_map: Option<Spanned<String>>,
+    reflectiveness_map: Option<Spanned<String>>,
+    refractiveness_map: Option<Spanned<String>>,
+    refractive_index_map: Option<Spanned<String>>,
+    emission_map: Option<Spanned<String>>,
+    // Tangent-space normal map, and a height map whose steepness is set by
+    // `bump_scale`
+    normal_map: Option<Spanned<String>>,
+    bump_map: Option<Spanned<String>>,
+    #[serde(default = "one")]
+    bump_scale: f32,
+    // Setting either switches to the physically based metallic-roughness
+    // model, which ignores the Phong weights below. Metallic defaults to 0
+    // and roughness to 0.5.
+    metallic: Option<f32>,
+    roughness: Option<f32>,
+    // glTF packing, roughness in green and metallic in blue
+    metallic_roughness_map: Option<Spanned<String>>,
+    #[serde(default = "one")]
+    diffuse: f32,
+    #[serde(default)]
+    specular: f32,
+    #[serde(default)]
+    specular_exp: f32,
+    #[serde(default)]
+    reflectiveness: f32,
+    #[serde(default)]
+    refractiveness: f32,
+    #[serde(default = "one")]
+    refractive_index: f32,
+    // Absorbed share of each channel per unit of distance travelled inside
+    #[serde(default)]
+    absorption: [f32; 3],
+    // Radiance given off by the front of the surface. Anything but black
+    // turns every shape using the material into a light.
+    #[serde(default)]
+    emission: [f32; 3],
+    // Shadow rays towards each such shape per shading point
+    #[serde(default = "light_samples")]
+    emission_samples: u32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Image {
+        // Relative to the scene file
+        file: String,
+        #[serde(default)]
+        wrap: WrapDesc,
+        // Repetitions per unit of UV
+        #[serde(default = "unit_uv_scale")]
+        scale: [f32; 2],
+        // Skips the sRGB decoding, for data such as normal maps
+        #[serde(default)]
+        linear: bool,
+    },
+    Checker {
+        #[serde(default = "white")]
+        even: [f32; 3],
+        #[serde(default)]
+        odd: [f32; 3],
+        // Cells per unit
+        #[serde(default = "one")]
+        scale: f32,
+        #[serde(default)]
+        space: SpaceDesc,
+    },
+    Gradient {
+        start: [f32; 3],
+        end: [f32; 3],
+        from: [f32; 3],
+        to: [f32; 3],
+        #[serde(default)]
+        space: SpaceDesc,
+    },
+    Noise {
+        #[serde(default)]
+        low: [f32; 3],
+        #[serde(default = "white")]
+        high: [f32; 3],
+        #[serde(default = "one")]
+        scale: f32,
+        #[serde(default = "one_octave")]
+        octaves: u32,
+        #[serde(default)]
+        turbulent: bool,
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        space: SpaceDesc,
+    },
+    Marble {
+        #[serde(default = "white")]
+        base: [f32; 3],
+        #[serde(default)]
+        vein: [f32; 3],
+        #[serde(default = "one")]
+        scale: f32,
+        #[serde(default = "marble_distortion")]
+        distortion: f32,
+        #[serde(default = "six_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        space: SpaceDesc,
+    },
+    Wood {
+        #[serde(default = "light_wood")]
+        light: [f32; 3],
+        #[serde(default = "dark_wood")]
+        dark: [f32; 3],
+        #[serde(default = "one")]
+        scale: f32,
+        // Rings per unit of radius
+        #[serde(default = "wood_rings")]
+        rings: f32,
+        #[serde(default = "one")]
+        distortion: f32,
+        #[serde(default)]
+        seed: u64,
+        #[serde(default)]
+        space: SpaceDesc,
+    },
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum SpaceDesc {
+    #[default]
+    World,
+    Uv,
+}
+
+fn one_octave() -> u32 {
+    1
+}
+
+fn six_octaves() -> u32 {
+    6
+}
+
+fn marble_distortion() -> f32 {
+    5.0
+}
+
+fn wood_rings() -> f32 {
+    4.0
+}
+
+fn light_wood() -> [f32; 3] {
+    [0.45, 0.25, 0.1]
+}
+
+fn dark_wood() -> [f32; 3] {
+    [0.2, 0.08, 0.02]
+}
+
+fn unit_uv_scale() -> [f32; 2] {
+    [1.0, 1.0]
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum WrapDesc {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+// Applied as scale, then rotation (degrees around X, Y then Z), then translation
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDesc {
+    #[serde(default)]
+    translate: [f32; 3],
+    #[serde(default)]
+    rotate: [f32; 3],
+    #[serde(default = "unit_scale")]
+    scale: [f32; 3],
+}
+
+fn unit_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDesc {
+    center: [f32; 3],
+    radius: f32,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlaneDesc {
+    position: [f32; 3],
+    normal: [f32; 3],
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskDesc {
+    position: [f32; 3],
+    normal: [f32; 3],
+    radius: f32,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CubeDesc {
+    min: [f32; 3],
+    max: [f32; 3],
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriangleDesc {
+    vertices: [[f32; 3]; 3],
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDesc>>,
+}
+
+// `file` is relative to the scene file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshDesc {
+    file: Spanned<String>,
+    // Only the faces under this `g` or `o` name
+    group: Option<Spanned<String>>,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightDesc {
+    position: Spanned<[f32; 3]>,
+    intensity: f32,
+    #[serde(flatten)]
+    color: LightColorDesc,
+    #[serde(flatten)]
+    attenuation: AttenuationFields,
+}
+
+// Shared by every light. Flattened fields lose their spans, so errors point
+// at the light instead
+#[derive(Deserialize)]
+struct LightColorDesc {
+    color: Option<[f32; 3]>,
+    // Kelvin, instead of a color
+    temperature: Option<f32>,
+}
+
+// Shared by the positioned lights. Without it the intensity reaches any
+// facing surface unchanged; for area lights, with it the intensity is the
+// radiance leaving the light
+#[derive(Deserialize)]
+struct AttenuationFields {
+    attenuation: Option<AttenuationDesc>,
+    // [constant, linear, quadratic], for `custom` attenuation
+    falloff: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AttenuationDesc {
+    None,
+    InverseSquare,
+    Custom,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpotLightDesc {
+    position: Spanned<[f32; 3]>,
+    // Either a point the spot is aimed at or the direction it shines in
+    target: Option<[f32; 3]>,
+    direction: Option<[f32; 3]>,
+    // Degrees from the axis
+    inner_angle: f32,
+    outer_angle: f32,
+    intensity: f32,
+    #[serde(flatten)]
+    color: LightColorDesc,
+    #[serde(flatten)]
+    attenuation: AttenuationFields,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DirectionalLightDesc {
+    // The way the light travels
+    direction: Spanned<[f32; 3]>,
+    intensity: f32,
+    #[serde(flatten)]
+    color: LightColorDesc,
+}
+
+fn texture_space(space: &SpaceDesc) -> TextureSpace {
+    match space {
+        SpaceDesc::World => TextureSpace::World,
+        SpaceDesc::Uv => TextureSpace::Uv,
+    }
+}
+
+// Shadow rays per shading point for area lights
+fn light_samples() -> u32 {
+    16
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RectLightDesc {
+    corner: [f32; 3],
+    // Emits towards edge_u x edge_v
+    edge_u: Spanned<[f32; 3]>,
+    edge_v: [f32; 3],
+    intensity: f32,
+    #[serde(flatten)]
+    color: LightColorDesc,
+    #[serde(flatten)]
+    attenuation: AttenuationFields,
+    #[serde(default = "light_samples")]
+    samples: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskLightDesc {
+    position: [f32; 3],
+    normal: Spanned<[f32; 3]>,
+    radius: f32,
+    intensity: f32,
+    #[serde(flatten)]
+    color: LightColorDesc,
+    #[serde(flatten)]
+    attenuation: AttenuationFields,
+    #[serde(default = "light_samples")]
+    samples: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereLightDesc {
+    center: Spanned<[f32; 3]>,
+    radius: f32,
+    intensity: f32,
+    #[serde(flatten)]
+    color: LightColorDesc,
+    #[serde(flatten)]
+    attenuation: AttenuationFields,
+    #[serde(default = "light_samples")]
+    samples: u32,
+}
+
+struct SceneBuilder<'a> {
+    path: &'a Path,
+    source: &'a str,
+    base_dir: PathBuf,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    materials: HashMap<String, material::Material>,
+    emission_samples: HashMap<String, u32>,
+    // Loaded once per file, group and material and shared by every entry
+    // that places it
+    meshes: HashMap<(String, Option<String>, String), Arc<shapes::TriangleMesh>>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    // Same shape as the messages produced by the TOML parser
+    fn error_at(&self, offset: usize, message: String) -> String {
+        let before = &self.source[..offset.min(self.source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |idx| idx + 1) + 1;
+        format!("{}: {} at line {} column {}", self.path.display(), message, line, column)
+    }
+
+    fn material(&self, name: &Spanned<String>) -> Result<material::Material, String> {
+        self.materials
+            .get(name.get_ref())
+            .cloned()
+            .ok_or_else(|| self.error_at(name.start(), format!("unknown material `{}`", name.get_ref())))
+    }
+
+    fn texture(&self, name: &Spanned<String>) -> Result<Arc<dyn Texture>, String> {
+        self.textures
+            .get(name.get_ref())
+            .cloned()
+            .ok_or_else(|| self.error_at(name.start(), format!("unknown texture `{}`", name.get_ref())))
+    }
+
+    fn build_texture(&self, desc: &Spanned<TextureDesc>) -> Result<Arc<dyn Texture>, String> {
+        match desc.get_ref() {
+            TextureDesc::Image { file, wrap, scale, linear } => {
+                let path = self.base_dir.join(file);
+                let texture = if *linear { ImageTexture::load_linear(path) } else { ImageTexture::load(path) };
+                let mut texture = texture.map_err(|err| self.error_at(desc.start(), err))?;
+                texture.set_wrap(match wrap {
+                    WrapDesc::Repeat => WrapMode::Repeat,
+                    WrapDesc::MirroredRepeat => WrapMode::MirroredRepeat,
+                    WrapDesc::ClampToEdge => WrapMode::ClampToEdge,
+                });
+                texture.set_scale((*scale).into());
+                Ok(Arc::new(texture))
+            }
+            TextureDesc::Checker { even, odd, scale, space } => {
+                Ok(Arc::new(Checker::new((*even).into(), (*odd).into(), *scale, texture_space(space))))
+            }
+            TextureDesc::Gradient { start, end, from, to, space } => {
+                let gradient = Gradient::new((*start).into(), (*end).into(), (*from).into(), (*to).into(), texture_space(space))
+                    .map_err(|err| self.error_at(desc.start(), err))?;
+                Ok(Arc::new(gradient))
+            }
+            TextureDesc::Noise { low, high, scale, octaves, turbulent, seed, space } => Ok(Arc::new(NoiseTexture::new(
+                Perlin::new(*seed),
+                (*low).into(),
+                (*high).into(),
+                *scale,
+                *octaves,
+                *turbulent,
+                texture_space(space),
+            ))),
+            TextureDesc::Marble { base, vein, scale, distortion, octaves, seed, space } => Ok(Arc::new(Marble::new(
+                Perlin::new(*seed),
+                (*base).into(),
+                (*vein).into(),
+                *scale,
+                *distortion,
+                *octaves,
+                texture_space(space),
+            ))),
+            TextureDesc::Wood { light, dark, scale, rings, distortion, seed, space } => Ok(Arc::new(Wood::new(
+                Perlin::new(*seed),
+                (*light).into(),
+                (*dark).into(),
+                *scale,
+                *rings,
+                *distortion,
+                texture_space(space),
+            ))),
+        }
+    }
+
+    fn light_color(&self, desc: &LightColorDesc, offset: usize) -> Result<Radiance, String> {
+        match (desc.color, desc.temperature) {
+            (Some(_), Some(_)) => Err(self.error_at(offset, String::from("light has both a color and a temperature"))),
+            (Some(color), None) => Ok(color.into()),
+            (None, Some(temperature)) => Ok(Radiance::from_temperature(temperature)),
+            (None, None) => Ok(Radiance::gray(1.0)),
+        }
+    }
+
+    fn attenuation(&self, desc: &AttenuationFields, offset: usize) -> Result<Attenuation, String> {
+        match desc.attenuation {
+            None | Some(AttenuationDesc::None) => Ok(Attenuation::None),
+            Some(AttenuationDesc::InverseSquare) => Ok(Attenuation::InverseSquare),
+            Some(AttenuationDesc::Custom) => match desc.falloff {
+                Some([constant, linear, quadratic]) => Ok(Attenuation::Custom { constant, linear, quadratic }),
+                None => Err(self.error_at(offset, String::from("custom attenuation needs a falloff"))),
+            },
+        }
+    }
+
+    // toml only spans values, this finds a `[name]` table header instead
+    fn table_offset(&self, name: &str) -> usize {
+        let header = format!("[{}]", name);
+        let mut offset = 0;
+        for line in self.source.split_inclusive('\n') {
+            if line.trim() == header {
+                return offset + line.len() - line.trim_start().len();
+            }
+            offset += line.len();
+        }
+        0
+    }
+
+    fn resolution(&self, size: &Option<Spanned<usize>>, default: usize) -> Result<usize, String> {
+        match size {
+            Some(size) if *size.get_ref() == 0 => Err(self.error_at(size.start(), String::from("image size must be positive"))),
+            Some(size) => Ok(*size.get_ref()),
+            None => Ok(default),
+        }
+    }
+
+    fn transform_matrix(t: &TransformDesc) -> Matrix4<f32> {
+        Matrix4::from_translation(Vector3::from(t.translate))
+            * Matrix4::from_angle_z(Deg(t.rotate[2]))
+            * Matrix4::from_angle_y(Deg(t.rotate[1]))
+            * Matrix4::from_angle_x(Deg(t.rotate[0]))
+            * Matrix4::from_nonuniform_scale(t.scale[0], t.scale[1], t.scale[2])
+    }
+
+    fn mesh(&mut self, desc: &MeshDesc) -> Result<Arc<shapes::TriangleMesh>, String> {
+        let key = (
+            desc.file.get_ref().clone(),
+            desc.group.as_ref().map(|group| group.get_ref().clone()),
+            desc.material.get_ref().clone(),
+        );
+        if let Some(mesh) = self.meshes.get(&key) {
+            return Ok(mesh.clone());
+        }
+        let mesh = super::load_obj(self.base_dir.join(desc.file.get_ref()), self.material(&desc.material)?)
+            .map_err(|err| self.error_at(desc.file.start(), err))?;
+        let mesh = match &desc.group {
+            Some(group) => mesh.group(group.get_ref()).ok_or_else(|| {
+                self.error_at(group.start(), format!("{} has no group named '{}'", desc.file.get_ref(), group.get_ref()))
+            })?,
+            None => mesh,
+        };
+        let mesh = Arc::new(mesh);
+        self.meshes.insert(key, mesh.clone());
+        Ok(mesh)
+    }
+
+    fn place(
+        &self,
+        object: Arc<dyn Traceable>,
+        transform: &Option<Spanned<TransformDesc>>,
+    ) -> Result<Box<dyn Traceable>, String> {
+        match transform {
+            Some(desc) => {
+                let transformed = Transformed::new(object, Self::transform_matrix(desc.get_ref()))
+                    .map_err(|err| self.error_at(desc.start(), err))?;
+                Ok(Box::new(transformed))
+            }
+            None => Ok(Box::new(object)),
+        }
+    }
+
+    // Places a finite shape, which also becomes a light if its material glows
+    fn add_shape(
+        &self,
+        scene: &mut Scene,
+        shape: Arc<dyn Sampleable>,
+        material_name: &Spanned<String>,
+        transform: &Option<Spanned<TransformDesc>>,
+    ) -> Result<(), String> {
+        scene.add_object(self.place(shape.clone(), transform)?);
+        if self.material(material_name)?.is_emissive() {
+            self.add_emitter(scene, shape, material_name, transform)?;
+        }
+        Ok(())
+    }
+
+    fn add_emitter(
+        &self,
+        scene: &mut Scene,
+        shape: Arc<dyn Sampleable>,
+        material_name: &Spanned<String>,
+        transform: &Option<Spanned<TransformDesc>>,
+    ) -> Result<(), String> {
+        let samples = self.emission_samples.get(material_name.get_ref()).copied().unwrap_or_else(light_samples);
+        let mut light = EmissiveLight::new(shape, self.material(material_name)?, samples);
+        if let Some(desc) = transform {
+            light
+                .set_transform(Self::transform_matrix(desc.get_ref()))
+                .map_err(|err| self.error_at(desc.start(), err))?;
+        }
+        scene.add_light(Box::new(light));
+        Ok(())
+    }
+}
+
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<LoadedScene, String> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    parse_scene(&source, path)
+}
+
+pub fn parse_scene(source: &str, path: &Path) -> Result<LoadedScene, String> {
+    let desc: SceneDesc = toml::from_str(source).map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    let mut builder = SceneBuilder {
+        path,
+        source,
+        base_dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        emission_samples: HashMap::new(),
+        meshes: HashMap::new(),
+    };
+    for (name, t) in desc.textures.iter() {
+        let texture = builder.build_texture(t)?;
+        builder.textures.insert(name.clone(), texture);
+    }
+    for (name, m) in desc.materials.iter() {
+        let mut material = material::Material::new(
+            m.color.into(),
+            m.diffuse,
+            m.specular,
+            m.specular_exp,
+            m.reflectiveness,
+            m.refractiveness,
+            m.refractive_index,
+        );
+        let map = |name: &Option<Spanned<String>>| name.as_ref().map(|name| builder.texture(name)).transpose();
+        material.albedo_map = map(&m.albedo_map)?;
+        material.diffuse_map = map(&m.diffuse_map)?;
+        material.specular_map = map(&m.specular_map)?;
+        material.specular_exp_map = map(&m.specular_exp_map)?;
+        material.reflectiveness_map = map(&m.reflectiveness_map)?;
+        material.refractiveness_map = map(&m.refractiveness_map)?;
+        material.refractive_index_map = map(&m.refractive_index_map)?;
+        material.emission_map = map(&m.emission_map)?;
+        material.normal_map = map(&m.normal_map)?;
+        material.bump_map = map(&m.bump_map)?;
+        material.bump_scale = m.bump_scale;
+        material.absorption = m.absorption.into();
+        material.emission = m.emission.into();
+        if m.metallic.is_some() || m.roughness.is_some() || m.metallic_roughness_map.is_some() {
+            let model = material::MetallicRoughness::new(m.metallic.unwrap_or(0.0), m.roughness.unwrap_or(0.5));
+            material.model = material::MaterialModel::MetallicRoughness(model);
+            material.metallic_roughness_map = map(&m.metallic_roughness_map)?;
+        }
+        builder.materials.insert(name.clone(), material);
+        builder.emission_samples.insert(name.clone(), m.emission_samples);
+    }
+
+    let mut scene = Scene::new();
+
+    for s in desc.sphere.iter() {
+        let object = shapes::Sphere::new(s.center.into(), s.radius, builder.material(&s.material)?);
+        builder.add_shape(&mut scene, Arc::new(object), &s.material, &s.transform)?;
+    }
+    for p in desc.plane.iter() {
+        let material = builder.material(&p.material)?;
+        if material.is_emissive() {
+            return Err(builder.error_at(p.material.start(), String::from("planes are infinite and can't be emissive")));
+        }
+        let object = Arc::new(shapes::Plane::new(p.position.into(), p.normal.into(), material));
+        scene.add_object(builder.place(object, &p.transform)?);
+    }
+    for d in desc.disk.iter() {
+        let object = shapes::Disk::new(d.position.into(), d.normal.into(), d.radius, builder.material(&d.material)?);
+        builder.add_shape(&mut scene, Arc::new(object), &d.material, &d.transform)?;
+    }
+    for c in desc.cube.iter() {
+        let object = shapes::Cube::new(c.min.into(), c.max.into(), builder.material(&c.material)?);
+        builder.add_shape(&mut scene, Arc::new(object), &c.material, &c.transform)?;
+    }
+    // Emissive triangles light the scene as one mesh per material, so a
+    // shape made of many of them doesn't cost a light per triangle
+    let mut emissive_triangles: Vec<(&Spanned<String>, Vec<Vector3<f32>>)> = Vec::new();
+    for t in desc.triangle.iter() {
+        let [v0, v1, v2] = t.vertices;
+        let object = shapes::Triangle::new(v0.into(), v1.into(), v2.into(), builder.material(&t.material)?);
+        if !builder.material(&t.material)?.is_emissive() {
+            builder.add_shape(&mut scene, Arc::new(object), &t.material, &t.transform)?;
+            continue;
+        }
+        scene.add_object(builder.place(Arc::new(object), &t.transform)?);
+        let transform = t.transform.as_ref().map_or(Matrix4::identity(), |desc| SceneBuilder::transform_matrix(desc.get_ref()));
+        let vertices = t.vertices.iter().map(|v| (transform * Vector4::new(v[0], v[1], v[2], 1.0)).truncate());
+        match emissive_triangles.iter_mut().find(|(name, _)| name.get_ref() == t.material.get_ref()) {
+            Some((_, positions)) => positions.extend(vertices),
+            None => emissive_triangles.push((&t.material, vertices.collect())),
+        }
+    }
+    for (material_name, positions) in emissive_triangles {
+        let faces = (0..positions.len() / 3)
+            .map(|i| shapes::MeshFace {
+                positions: [3 * i, 3 * i + 1, 3 * i + 2],
+                normals: None,
+                uvs: None,
+            })
+            .collect();
+        let mesh = shapes::TriangleMesh::new(positions, Vec::new(), Vec::new(), faces, Vec::new(), builder.material(material_name)?);
+        builder.add_emitter(&mut scene, Arc::new(mesh), material_name, &None)?;
+    }
+    for m in desc.mesh.iter() {
+        let object = builder.mesh(m)?;
+        builder.add_shape(&mut scene, object, &m.material, &m.transform)?;
+    }
+
+    for l in desc.light.iter() {
+        let mut light = PointLight::new((*l.position.get_ref()).into(), l.intensity);
+        light.set_attenuation(builder.attenuation(&l.attenuation, l.position.start())?);
+        light.set_color(builder.light_color(&l.color, l.position.start())?);
+        scene.add_light(Box::new(light));
+    }
+    for l in desc.spot_light.iter() {
+        let position = Vector3::from(*l.position.get_ref());
+        let direction = match (l.target, l.direction) {
+            (Some(target), None) => Vector3::from(target) - position,
+            (None, Some(direction)) => direction.into(),
+            _ => return Err(builder.error_at(l.position.start(), String::from("spot light needs either a target or a direction"))),
+        };
+        let mut light = SpotLight::new(position, direction, l.inner_angle, l.outer_angle, l.intensity)
+            .map_err(|err| builder.error_at(l.position.start(), err))?;
+        light.set_attenuation(builder.attenuation(&l.attenuation, l.position.start())?);
+        light.set_color(builder.light_color(&l.color, l.position.start())?);
+        scene.add_light(Box::new(light));
+    }
+    for l in desc.directional_light.iter() {
+        let mut light = DirectionalLight::new((*l.direction.get_ref()).into(), l.intensity)
+            .map_err(|err| builder.error_at(l.direction.start(), err))?;
+        light.set_color(builder.light_color(&l.color, l.direction.start())?);
+        scene.add_light(Box::new(light));
+    }
+    for l in desc.rect_light.iter() {
+        let mut light = RectLight::new(l.corner.into(), (*l.edge_u.get_ref()).into(), l.edge_v.into(), l.intensity, l.samples)
+            .map_err(|err| builder.error_at(l.edge_u.start(), err))?;
+        light.set_attenuation(builder.attenuation(&l.attenuation, l.edge_u.start())?);
+        light.set_color(builder.light_color(&l.color, l.edge_u.start())?);
+        scene.add_light(Box::new(light));
+    }
+    for l in desc.disk_light.iter() {
+        let mut light = DiskLight::new(l.position.into(), (*l.normal.get_ref()).into(), l.radius, l.intensity, l.samples)
+            .map_err(|err| builder.error_at(l.normal.start(), err))?;
+        light.set_attenuation(builder.attenuation(&l.attenuation, l.normal.start())?);
+        light.set_color(builder.light_color(&l.color, l.normal.start())?);
+        scene.add_light(Box::new(light));
+    }
+    for l in desc.sphere_light.iter() {
+        let mut light = SphereLight::new((*l.center.get_ref()).into(), l.radius, l.intensity, l.samples);
+        light.set_attenuation(builder.attenuation(&l.attenuation, l.center.start())?);
+        light.set_color(builder.light_color(&l.color, l.center.start())?);
+        scene.add_light(Box::new(light));
+    }
+    if let Some(background) = desc.render.background {
+        scene.set_background(background.into());
+    }
+
+    let camera_desc = &desc.camera;
+    let camera_offset = builder.table_offset("camera");
+    let camera_error = |message: &str| builder.error_at(camera_offset, String::from(message));
+    let projection = match camera_desc.projection {
+        ProjectionDesc::Perspective => Projection::Perspective {
+            fov: camera_desc.fov.ok_or_else(|| camera_error("perspective camera needs a fov"))?,
+            fov_axis: match camera_desc.fov_axis {
+                FovAxisDesc::Horizontal => FovAxis::Horizontal,
+                FovAxisDesc::Vertical => FovAxis::Vertical,
+            },
+        },
+        ProjectionDesc::Orthographic => Projection::Orthographic {
+            height: camera_desc.height.ok_or_else(|| camera_error("orthographic camera needs a height"))?,
+        },
+        ProjectionDesc::Fisheye => Projection::Fisheye {
+            fov: camera_desc.fov.unwrap_or(180.0),
+        },
+        ProjectionDesc::Equirectangular => Projection::Equirectangular,
+    };
+
+    let origin = Vector3::from(camera_desc.origin);
+    let mut camera = Camera::new(origin, 0.0);
+    camera.set_projection(projection);
+    let up = camera_desc.up.as_ref().map_or(Vector3::unit_y(), |up| (*up.get_ref()).into());
+    match &camera_desc.target {
+        Some(target) => camera.look_at((*target.get_ref()).into(), up),
+        None => camera.set_direction(-Vector3::unit_z(), up),
+    }
+    .map_err(|err| {
+        let offset = match (&camera_desc.target, &camera_desc.up) {
+            (Some(target), _) => target.start(),
+            (None, Some(up)) => up.start(),
+            (None, None) => camera_offset,
+        };
+        builder.error_at(offset, err)
+    })?;
+    camera.set_roll(camera_desc.roll);
+    camera.set_aspect(camera_desc.aspect);
+    if let Some(aperture) = camera_desc.aperture.as_ref().filter(|aperture| *aperture.get_ref() != 0.0) {
+        let focus_distance = match (&camera_desc.focus_distance, &camera_desc.target) {
+            (Some(focus_distance), _) => *focus_distance.get_ref(),
+            (None, Some(target)) => (Vector3::from(*target.get_ref()) - origin).magnitude(),
+            (None, None) => {
+                return Err(builder.error_at(aperture.start(), String::from("camera aperture needs a focus_distance or target")))
+            }
+        };
+        camera
+            .set_depth_of_field(*aperture.get_ref(), focus_distance)
+            .map_err(|err| builder.error_at(aperture.start(), err))?;
+    }
+    let width = builder.resolution(&desc.render.width, 800)?;
+    let height = builder.resolution(&desc.render.height, 600)?;
+    camera.set_samples_per_pixel(desc.render.samples);
+    camera.set_sample_pattern(match desc.render.sampler {
+        SamplerDesc::Jittered => SamplePattern::Jittered,
+        SamplerDesc::Stratified => SamplePattern::Stratified,
+    });
+    camera.set_filter(match desc.render.filter {
+        FilterDesc::Box => Filter::Box,
+        FilterDesc::Tent => Filter::Tent,
+        FilterDesc::Gaussian => Filter::Gaussian,
+        FilterDesc::Mitchell => Filter::Mitchell,
+    });
+    camera.set_integrator(match desc.render.integrator {
+        IntegratorDesc::Whitted => Box::new(Whitted::new(desc.render.max_depth.unwrap_or(4))),
+        IntegratorDesc::Path => Box::new(PathTracer::new(desc.render.max_depth.unwrap_or(16))),
+    });
+
+    Ok(LoadedScene {
+        scene,
+        camera,
+        width,
+        height,
+        tone_map: match desc.render.tone_map {
+            ToneMapDesc::Clamp => ToneMap::Clamp,
+            ToneMapDesc::Reinhard => ToneMap::Reinhard,
+            ToneMapDesc::ExtendedReinhard => ToneMap::ExtendedReinhard { white: desc.render.white },
+            ToneMapDesc::Aces => ToneMap::Aces,
+        },
+        exposure: desc.render.exposure,
+    })
+}
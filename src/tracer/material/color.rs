@@ -26,36 +26,18 @@ impl Default for Color {
     }
 }
 
-impl std::ops::Add<Color> for Color {
-    type Output = Color;
-    fn add(self, other: Color) -> <Self as std::ops::Add<Color>>::Output {
-        Color::RGB(
-            (self.r as u16 + other.r as u16).min(255).max(0) as u8,
-            (self.g as u16 + other.g as u16).min(255).max(0) as u8,
-            (self.b as u16 + other.b as u16).min(255).max(0) as u8,
-        )
+pub fn srgb_encode(linear: f32) -> f32 {
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
     }
 }
 
-impl std::ops::Mul<f32> for Color {
-    type Output = Color;
-    fn mul(self, scale: f32) -> <Self as std::ops::Mul<f32>>::Output {
-        let new_r = self.r as f32 * scale;
-        let new_g = self.g as f32 * scale;
-        let new_b = self.b as f32 * scale;
-
-        let mut color_scale: f32 = 1.0;
-        let max_cmp = new_r.max(new_g.max(new_b));
-        if max_cmp > 255.0 {
-            color_scale = 255.0 / max_cmp;
-        }
-
-        Color {
-            r: (new_r * color_scale) as u8,
-            g: (new_g * color_scale) as u8,
-            b: (new_b * color_scale) as u8,
-            a: 255,
-        }
+pub fn srgb_decode(encoded: f32) -> f32 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
     }
 }
-
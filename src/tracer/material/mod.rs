@@ -1,39 +1,94 @@
 mod color;
+mod radiance;
+mod pbr;
 pub use color::Color;
+pub use radiance::Radiance;
+pub use pbr::MetallicRoughness;
 
-#[derive(Clone, Copy)]
+use cgmath::prelude::*;
+use cgmath::{Vector2, Vector3};
+
+use std::sync::Arc;
+
+use super::ops;
+use super::texture::Texture;
+use super::types::RayHit;
+
+// How a material reflects light
+#[derive(Clone, Copy, Debug)]
+pub enum MaterialModel {
+    // The ad-hoc Phong weights of `Material`
+    Phong,
+    // Physically based, ignores the Phong weights and only uses `base_color`
+    MetallicRoughness(MetallicRoughness),
+}
+
+#[derive(Clone)]
 pub struct Material {
-    pub base_color: Color,
+    pub model: MaterialModel,
+    pub base_color: Radiance,
     pub diffuse_reflection: f32,
     pub specular_reflection: f32,
     pub specular_exp: f32,
     pub reflectiveness: f32,
     pub refractiveness: f32,
     pub refractive_index: f32,
+    // Beer-Lambert absorption inside the object per unit of distance, zero
+    // for clear materials
+    pub absorption: Radiance,
+    // Radiance given off by the front of the surface
+    pub emission: Radiance,
+    // Textures multiplying the constants above, scalars use the luminance
+    pub albedo_map: Option<Arc<dyn Texture>>,
+    pub diffuse_map: Option<Arc<dyn Texture>>,
+    pub specular_map: Option<Arc<dyn Texture>>,
+    pub specular_exp_map: Option<Arc<dyn Texture>>,
+    pub reflectiveness_map: Option<Arc<dyn Texture>>,
+    pub refractiveness_map: Option<Arc<dyn Texture>>,
+    pub refractive_index_map: Option<Arc<dyn Texture>>,
+    pub emission_map: Option<Arc<dyn Texture>>,
+    // Tangent-space normals stored as colors, X along u, Y along v
+    pub normal_map: Option<Arc<dyn Texture>>,
+    // Heights from the luminance, `bump_scale` sets how steep they get
+    pub bump_map: Option<Arc<dyn Texture>>,
+    pub bump_scale: f32,
+    // glTF packing, roughness in green and metallic in blue, multiplying the
+    // metallic-roughness model's constants
+    pub metallic_roughness_map: Option<Arc<dyn Texture>>,
 }
 
 impl Default for Material {
     fn default() -> Material {
         Material {
-            base_color: Color {
-                r: 0,
-                g: 0,
-                b: 0,
-                a: 255,
-            },
+            model: MaterialModel::Phong,
+            base_color: Radiance::black(),
             diffuse_reflection: 1_f32,
             specular_reflection: 0_f32,
             specular_exp: 0_f32,
             reflectiveness: 0_f32,
             refractiveness: 0_f32,
             refractive_index: 1_f32,
+            absorption: Radiance::black(),
+            emission: Radiance::black(),
+            albedo_map: None,
+            diffuse_map: None,
+            specular_map: None,
+            specular_exp_map: None,
+            reflectiveness_map: None,
+            refractiveness_map: None,
+            refractive_index_map: None,
+            emission_map: None,
+            normal_map: None,
+            bump_map: None,
+            bump_scale: 1_f32,
+            metallic_roughness_map: None,
         }
     }
 }
 
 impl Material {
     pub fn new(
-        base_color: Color,
+        base_color: Radiance,
         diffuse_reflection: f32,
         specular_reflection: f32,
         specular_exp: f32,
@@ -49,6 +104,88 @@ impl Material {
             reflectiveness,
             refractiveness,
             refractive_index,
+            ..Default::default()
+        }
+    }
+
+    // The material at one point of a surface, with every map looked up so
+    // shading only has to deal with constants
+    pub fn resolve(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Material {
+        let scalar = |value: f32, map: &Option<Arc<dyn Texture>>| match map {
+            Some(texture) => value * texture.sample(uv, point).luminance(),
+            None => value,
+        };
+        let model = match (self.model, &self.metallic_roughness_map) {
+            (MaterialModel::MetallicRoughness(m), Some(texture)) => {
+                let texel = texture.sample(uv, point);
+                MaterialModel::MetallicRoughness(MetallicRoughness::new(m.metallic * texel.b, m.roughness * texel.g))
+            }
+            (model, _) => model,
+        };
+        Material {
+            model,
+            base_color: match &self.albedo_map {
+                Some(texture) => self.base_color * texture.sample(uv, point),
+                None => self.base_color,
+            },
+            diffuse_reflection: scalar(self.diffuse_reflection, &self.diffuse_map),
+            specular_reflection: scalar(self.specular_reflection, &self.specular_map),
+            specular_exp: scalar(self.specular_exp, &self.specular_exp_map),
+            reflectiveness: scalar(self.reflectiveness, &self.reflectiveness_map),
+            refractiveness: scalar(self.refractiveness, &self.refractiveness_map),
+            refractive_index: scalar(self.refractive_index, &self.refractive_index_map),
+            absorption: self.absorption,
+            emission: self.emission_at(uv, point),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        self.emission.max_component() > 0.0
+    }
+
+    pub fn emission_at(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance {
+        match &self.emission_map {
+            Some(texture) => self.emission * texture.sample(uv, point),
+            None => self.emission,
+        }
+    }
+
+    // Share of light that makes it through `distance` of the material
+    pub fn transmittance(&self, distance: f32) -> Radiance {
+        self.absorption.map(|sigma| (-sigma * distance).exp())
+    }
+
+    // The hit's normal with the normal and bump maps applied
+    pub fn shading_normal(&self, hit: &RayHit) -> Vector3<f32> {
+        if self.normal_map.is_none() && self.bump_map.is_none() {
+            return hit.normal;
+        }
+        let (tangent, bitangent) = ops::tangent_frame(hit.normal, hit.tangent, hit.bitangent);
+        let mut normal = hit.normal;
+
+        if let Some(texture) = &self.normal_map {
+            let n = texture.sample(hit.uv, hit.hit);
+            normal = tangent * (2.0 * n.r - 1.0) + bitangent * (2.0 * n.g - 1.0) + normal * (2.0 * n.b - 1.0);
+        }
+
+        if let Some(texture) = &self.bump_map {
+            // Forward differences of the height along u and v
+            const STEP: f32 = 1e-3;
+            let height = |du: f32, dv: f32| {
+                let uv = hit.uv + Vector2::new(du, dv);
+                let point = hit.hit + tangent * du + bitangent * dv;
+                texture.sample(uv, point).luminance()
+            };
+            let h = height(0.0, 0.0);
+            let dh_du = (height(STEP, 0.0) - h) / STEP;
+            let dh_dv = (height(0.0, STEP) - h) / STEP;
+            normal -= (tangent * dh_du + bitangent * dh_dv) * self.bump_scale;
+        }
+
+        if normal.magnitude2() < 1e-12 {
+            return hit.normal;
         }
+        normal.normalize()
     }
 }
@@ -0,0 +1,146 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+use super::Radiance;
+use crate::tracer::ops;
+use crate::tracer::sampling::{cosine_hemisphere, ggx_half_vector};
+
+// Reflectance of dielectrics at normal incidence
+const DIELECTRIC_F0: f32 = 0.04;
+
+// Below this GGX turns into a spike that no sample ever hits
+const MIN_ALPHA: f32 = 1e-3;
+
+// The glTF metallic-roughness model: a Lambertian base under a GGX specular
+// layer with Smith masking and Schlick's Fresnel. Metals tint the specular
+// layer with the base color and have no diffuse part.
+#[derive(Clone, Copy, Debug)]
+pub struct MetallicRoughness {
+    pub metallic: f32,
+    // Perceptual roughness, squared to get GGX's alpha
+    pub roughness: f32,
+}
+
+// A direction picked by `MetallicRoughness::sample`, `weight` is the BRDF
+// times the cosine over the pdf
+pub struct BsdfSample {
+    pub direction: Vector3<f32>,
+    pub weight: Radiance,
+}
+
+impl MetallicRoughness {
+    pub fn new(metallic: f32, roughness: f32) -> Self {
+        Self {
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(0.0, 1.0),
+        }
+    }
+
+    fn alpha(&self) -> f32 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    fn f0(&self, base_color: Radiance) -> Radiance {
+        Radiance::gray(DIELECTRIC_F0) * (1.0 - self.metallic) + base_color * self.metallic
+    }
+
+    // Odds of sampling the specular lobe rather than the diffuse one, from
+    // how much each reflects when seen along `wo`
+    fn specular_probability(&self, base_color: Radiance, cos_o: f32) -> f32 {
+        let f = fresnel(self.f0(base_color), cos_o);
+        let specular = f.luminance();
+        let diffuse = ((Radiance::gray(1.0) - f) * base_color).luminance() * (1.0 - self.metallic);
+        if specular + diffuse <= 0.0 {
+            return 1.0;
+        }
+        (specular / (specular + diffuse)).clamp(0.1, 1.0)
+    }
+
+    // The BRDF for light arriving along `wi` and leaving along `wo`, both
+    // pointing away from the surface
+    pub fn eval(&self, base_color: Radiance, normal: Vector3<f32>, wo: Vector3<f32>, wi: Vector3<f32>) -> Radiance {
+        let cos_o = normal.dot(wo);
+        let cos_i = normal.dot(wi);
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return Radiance::black();
+        }
+        let h = (wo + wi).normalize();
+        let alpha = self.alpha();
+        let f0 = self.f0(base_color);
+        let specular = fresnel(f0, wo.dot(h).max(0.0)) * (ggx(normal.dot(h), alpha) * smith_visibility(cos_o, cos_i, alpha));
+        // Light enters and leaves the base through the specular layer, so it
+        // only gets what that layer lets through both ways
+        let transmitted = (Radiance::gray(1.0) - fresnel(f0, cos_o)) * (Radiance::gray(1.0) - fresnel(f0, cos_i));
+        let diffuse = transmitted * base_color * ((1.0 - self.metallic) / PI);
+        diffuse + specular
+    }
+
+    // Density of `sample` picking `wi`, per unit solid angle
+    pub fn pdf(&self, base_color: Radiance, normal: Vector3<f32>, wo: Vector3<f32>, wi: Vector3<f32>) -> f32 {
+        let cos_o = normal.dot(wo);
+        let cos_i = normal.dot(wi);
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return 0.0;
+        }
+        let h = (wo + wi).normalize();
+        let p = self.specular_probability(base_color, cos_o);
+        let specular = ggx(normal.dot(h), self.alpha()) * normal.dot(h) / (4.0 * wo.dot(h).max(1e-6));
+        p * specular + (1.0 - p) * cos_i / PI
+    }
+
+    // Importance samples the BRDF, `pick` chooses the lobe and `u`, `v` the
+    // direction within it
+    pub fn sample(&self, base_color: Radiance, normal: Vector3<f32>, wo: Vector3<f32>, pick: f32, u: f32, v: f32) -> Option<BsdfSample> {
+        let cos_o = normal.dot(wo);
+        if cos_o <= 0.0 {
+            return None;
+        }
+        let direction = if pick < self.specular_probability(base_color, cos_o) {
+            let h = ops::to_world(ggx_half_vector(u, v, self.alpha()), normal);
+            ops::reflect(-wo, h)
+        } else {
+            ops::to_world(cosine_hemisphere(u, v), normal)
+        };
+        let cos_i = normal.dot(direction);
+        let pdf = self.pdf(base_color, normal, wo, direction);
+        if cos_i <= 0.0 || pdf <= 0.0 {
+            return None;
+        }
+        let weight = self.eval(base_color, normal, wo, direction) * (cos_i / pdf);
+        Some(BsdfSample { direction, weight })
+    }
+
+    // Share of light a smooth surface sends back as a mirror image, for
+    // integrators that can only follow the perfect reflection
+    pub fn mirror_reflectance(&self, base_color: Radiance, cos_o: f32) -> Radiance {
+        let smoothness = 1.0 - self.roughness;
+        fresnel(self.f0(base_color), cos_o.max(0.0)) * (smoothness * smoothness)
+    }
+}
+
+// Trowbridge-Reitz normal distribution
+fn ggx(cos_h: f32, alpha: f32) -> f32 {
+    if cos_h <= 0.0 {
+        return 0.0;
+    }
+    let a2 = alpha * alpha;
+    let d = cos_h * cos_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+// Height-correlated Smith masking-shadowing, folded together with the
+// 1 / (4 cos_o cos_i) of the microfacet BRDF
+fn smith_visibility(cos_o: f32, cos_i: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let o = cos_i * (cos_o * cos_o * (1.0 - a2) + a2).sqrt();
+    let i = cos_o * (cos_i * cos_i * (1.0 - a2) + a2).sqrt();
+    0.5 / (o + i)
+}
+
+// Schlick's approximation
+fn fresnel(f0: Radiance, cos_theta: f32) -> Radiance {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    f0 + (Radiance::gray(1.0) - f0) * m
+}
@@ -0,0 +1,142 @@
+use super::color::{srgb_decode, srgb_encode};
+use super::Color;
+
+// Linear RGB radiance. Unbounded, only clamped when written out as a `Color`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Radiance {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Radiance {
+    pub fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn black() -> Self {
+        Self::default()
+    }
+
+    pub fn gray(value: f32) -> Self {
+        Self::new(value, value, value)
+    }
+
+    // Color of a blackbody radiator at `kelvin`, normalized to a luminance
+    // of 1. Uses Kang et al.'s fit of the Planckian locus, valid from 1667K
+    // to 25000K, and converts the chromaticity to linear sRGB.
+    pub fn from_temperature(kelvin: f32) -> Self {
+        // The fit's coefficients need more precision than f32 has
+        let t = kelvin.clamp(1667.0, 25000.0) as f64;
+        let x = if t <= 4000.0 {
+            -0.2661239e9 / t.powi(3) - 0.2343589e6 / t.powi(2) + 0.8776956e3 / t + 0.179910
+        } else {
+            -3.0258469e9 / t.powi(3) + 2.1070379e6 / t.powi(2) + 0.2226347e3 / t + 0.240390
+        };
+        let y = if t <= 2222.0 {
+            -1.1063814 * x.powi(3) - 1.34811020 * x.powi(2) + 2.18555832 * x - 0.20219683
+        } else if t <= 4000.0 {
+            -0.9549476 * x.powi(3) - 1.37418593 * x.powi(2) + 2.09137015 * x - 0.16748867
+        } else {
+            3.0817580 * x.powi(3) - 5.87338670 * x.powi(2) + 3.75112997 * x - 0.37001483
+        };
+
+        // XYZ with Y = 1
+        let cx = x / y;
+        let cz = (1.0 - x - y) / y;
+        let rgb = Self::new(
+            (3.2404542 * cx - 1.5371385 - 0.4985314 * cz) as f32,
+            (-0.9692660 * cx + 1.8760108 + 0.0415560 * cz) as f32,
+            (0.0556434 * cx - 0.2040259 + 1.0572252 * cz) as f32,
+        )
+        .map(|c| c.max(0.0));
+        rgb / rgb.luminance()
+    }
+
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn max_component(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    pub fn map<F: Fn(f32) -> f32>(&self, f: F) -> Self {
+        Self::new(f(self.r), f(self.g), f(self.b))
+    }
+}
+
+impl From<[f32; 3]> for Radiance {
+    fn from(rgb: [f32; 3]) -> Self {
+        Self::new(rgb[0], rgb[1], rgb[2])
+    }
+}
+
+// 8-bit colors are sRGB encoded
+impl From<Color> for Radiance {
+    fn from(color: Color) -> Self {
+        Self::new(
+            srgb_decode(color.r as f32 / 255.0),
+            srgb_decode(color.g as f32 / 255.0),
+            srgb_decode(color.b as f32 / 255.0),
+        )
+    }
+}
+
+impl From<Radiance> for Color {
+    fn from(radiance: Radiance) -> Self {
+        // `as` saturates, so anything outside [0, 1] is clamped
+        Color::RGB(
+            (srgb_encode(radiance.r) * 255.0).round() as u8,
+            (srgb_encode(radiance.g) * 255.0).round() as u8,
+            (srgb_encode(radiance.b) * 255.0).round() as u8,
+        )
+    }
+}
+
+impl std::ops::Add<Radiance> for Radiance {
+    type Output = Radiance;
+    fn add(self, other: Radiance) -> Radiance {
+        Radiance::new(self.r + other.r, self.g + other.g, self.b + other.b)
+    }
+}
+
+impl std::ops::AddAssign<Radiance> for Radiance {
+    fn add_assign(&mut self, other: Radiance) {
+        *self = *self + other;
+    }
+}
+
+impl std::ops::Sub<Radiance> for Radiance {
+    type Output = Radiance;
+    fn sub(self, other: Radiance) -> Radiance {
+        Radiance::new(self.r - other.r, self.g - other.g, self.b - other.b)
+    }
+}
+
+impl std::ops::Mul<Radiance> for Radiance {
+    type Output = Radiance;
+    fn mul(self, other: Radiance) -> Radiance {
+        Radiance::new(self.r * other.r, self.g * other.g, self.b * other.b)
+    }
+}
+
+impl std::ops::MulAssign<Radiance> for Radiance {
+    fn mul_assign(&mut self, other: Radiance) {
+        *self = *self * other;
+    }
+}
+
+impl std::ops::Mul<f32> for Radiance {
+    type Output = Radiance;
+    fn mul(self, scale: f32) -> Radiance {
+        Radiance::new(self.r * scale, self.g * scale, self.b * scale)
+    }
+}
+
+impl std::ops::Div<f32> for Radiance {
+    type Output = Radiance;
+    fn div(self, scale: f32) -> Radiance {
+        Radiance::new(self.r / scale, self.g / scale, self.b / scale)
+    }
+}
@@ -1,25 +1,40 @@
 mod material;
 pub mod traceable;
+pub mod loader;
+pub mod tonemap;
+pub mod sampling;
+pub mod projection;
+pub mod integrator;
+pub mod light;
+pub mod texture;
 
 pub mod types {
     pub use super::material::Material as Material;
+    pub use super::material::{MaterialModel, MetallicRoughness};
     pub use super::material::Color as Color;
-    use super::traceable::Traceable;
+    pub use super::material::Radiance as Radiance;
+    pub use super::tonemap::ToneMap;
+    pub use super::sampling::{Filter, SamplePattern};
+    pub use super::projection::{FovAxis, Projection};
+    pub use super::integrator::{Integrator, PathTracer, Whitted};
+    pub use super::light::{Attenuation, DirectionalLight, DiskLight, Illumine, PointLight, RectLight, SphereLight, SpotLight, EmissiveLight};
+    use super::sampling::{concentric_disk, Rng};
+    use super::traceable::{Aabb, Bvh, Traceable};
 
-    use cgmath::dot;
     use cgmath::prelude::*;
-    use cgmath::Vector3;
-
-    pub trait Illumine {
-        fn get_direction(&self, at: Vector3<f32>) -> Vector3<f32>;
-        fn get_position(&self) -> Vector3<f32>;
-        fn get_intensity(&self) -> f32;
-    }
+    use cgmath::{Deg, Matrix3, Vector2, Vector3};
 
     pub struct RayHit {
         pub distance: f32,
         pub hit: Vector3<f32>,
         pub normal: Vector3<f32>,
+        pub barycentric: Vector2<f32>,
+        // Surface parameterization used to look up textures
+        pub uv: Vector2<f32>,
+        // Directions in which u and v grow along the surface, not necessarily
+        // unit length or perpendicular to the normal
+        pub tangent: Vector3<f32>,
+        pub bitangent: Vector3<f32>,
         pub material: Material,
     }
 
@@ -29,40 +44,52 @@ pub mod types {
                 distance: f32::MAX,
                 hit: Vector3::zero(),
                 normal: Vector3::zero(),
+                barycentric: Vector2::zero(),
+                uv: Vector2::zero(),
+                tangent: Vector3::zero(),
+                bitangent: Vector3::zero(),
                 material: Material::default(),
             }
         }
     }
 
-    pub struct Light {
-        position: Vector3<f32>,
-        intensity: f32,
+    // Objects with a bounding box live in the BVH, the rest (planes) are
+    // tested against every ray
+    struct SceneAccel {
+        bvh: Bvh,
+        bounded: Vec<usize>,
+        unbounded: Vec<usize>,
     }
 
-    impl Light {
-        pub fn new(position: Vector3<f32>, intensity: f32) -> Self {
+    impl SceneAccel {
+        fn build(objects: &[Box<dyn Traceable>]) -> Self {
+            let mut bounds = Vec::new();
+            let mut bounded = Vec::new();
+            let mut unbounded = Vec::new();
+            for (idx, object) in objects.iter().enumerate() {
+                match object.bounding_box() {
+                    Some(aabb) => {
+                        bounds.push(aabb);
+                        bounded.push(idx);
+                    }
+                    None => unbounded.push(idx),
+                }
+            }
             Self {
-                position,
-                intensity,
+                bvh: Bvh::build(&bounds),
+                bounded,
+                unbounded,
             }
         }
     }
 
-    impl Illumine for Light {
-        fn get_direction(&self, at: Vector3<f32>) -> Vector3<f32> {
-            (self.position - at).normalize()
-        }
-        fn get_intensity(&self) -> f32 {
-            self.intensity
-        }
-        fn get_position(&self) -> Vector3<f32> {
-            self.position
-        }
-    }
-
     pub struct Scene {
         objects: Vec<Box<dyn super::traceable::Traceable>>,
         lights: Vec<Box<dyn Illumine>>,
+        // Seen by rays that leave the scene
+        background: Radiance,
+        // Built lazily on the first intersection after the objects change
+        accel: OnceLock<SceneAccel>,
     }
 
     unsafe impl Sync for Scene {}
@@ -72,11 +99,14 @@ pub mod types {
             Self {
                 objects: Vec::new(),
                 lights: Vec::new(),
+                background: Radiance::from(Color::RGB(155, 200, 100)),
+                accel: OnceLock::new(),
             }
         }
 
         pub fn add_object(&mut self, object: Box<dyn super::traceable::Traceable>) -> &mut Self {
             self.objects.push(object);
+            self.accel = OnceLock::new();
             self
         }
 
@@ -84,15 +114,38 @@ pub mod types {
             self.lights.push(light);
             self
         }
+
+        pub fn lights(&self) -> &[Box<dyn Illumine>] {
+            &self.lights
+        }
+
+        pub fn set_background(&mut self, background: Radiance) -> &mut Self {
+            self.background = background;
+            self
+        }
+
+        pub fn background(&self) -> Radiance {
+            self.background
+        }
     }
 
     impl Traceable for Scene {
         fn ray_intersect(&self, ray: &Ray) -> std::option::Option<RayHit> {
+            let accel = self.accel.get_or_init(|| SceneAccel::build(&self.objects));
+
             let mut hit_res = RayHit::default();
             hit_res.distance = f32::MAX;
             let mut found = false;
-            self.objects.iter().for_each(|object| {
-                if let Some(hit_data) = object.ray_intersect(ray) {
+            if let Some((_, hit_data)) = accel.bvh.traverse(ray, |idx| {
+                self.objects[accel.bounded[idx]]
+                    .ray_intersect(ray)
+                    .map(|hit_data| (hit_data.distance, hit_data))
+            }) {
+                hit_res = hit_data;
+                found = true;
+            }
+            accel.unbounded.iter().for_each(|&idx| {
+                if let Some(hit_data) = self.objects[idx].ray_intersect(ray) {
                     if hit_data.distance < hit_res.distance {
                         hit_res = hit_data;
                         found = true;
@@ -105,6 +158,14 @@ pub mod types {
                 None
             }
         }
+
+        fn bounding_box(&self) -> Option<Aabb> {
+            self.objects
+                .iter()
+                .try_fold(Aabb::empty(), |aabb, object| {
+                    object.bounding_box().map(|bounds| aabb.union(&bounds))
+                })
+        }
     }
 
     pub struct Ray {
@@ -122,6 +183,11 @@ pub mod types {
         width: usize,
         height: usize,
         pub pixels: Vec<u8>,
+        // Running mean of every frame since the last reset, in radiance
+        accumulation: Vec<Radiance>,
+        pub tone_map: ToneMap,
+        // In stops, applied before tone mapping
+        pub exposure: f32,
     }
 
     impl PixelData {
@@ -130,15 +196,78 @@ pub mod types {
                 width,
                 height,
                 pixels: vec![0; width * height * 4],
+                accumulation: vec![Radiance::black(); width * height],
+                tone_map: ToneMap::default(),
+                exposure: 0.0,
             }
         }
-        pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        pub fn set_pixel(&mut self, x: usize, y: usize, radiance: Radiance) {
+            let exposed = radiance * super::tonemap::exposure_scale(self.exposure);
+            let color = Color::from(self.tone_map.apply(exposed));
             let pixel_idx: usize = (x + (y * self.width)) * 4;
             self.pixels[pixel_idx + 0] = color.b;
             self.pixels[pixel_idx + 1] = color.g;
             self.pixels[pixel_idx + 2] = color.r;
             self.pixels[pixel_idx + 3] = color.a;
         }
+
+        // `tile` holds rows of `tile_width` pixels starting at (x, y). It is
+        // averaged with the `frame` previous ones, frame 0 replaces them.
+        pub fn accumulate_tile(&mut self, x: usize, y: usize, tile_width: usize, tile: &[Radiance], frame: u32) {
+            let weight = 1.0 / (frame + 1) as f32;
+            for (idx, radiance) in tile.iter().enumerate() {
+                let px = x + idx % tile_width;
+                let py = y + idx / tile_width;
+                let acc_idx = px + py * self.width;
+                let mean = if frame == 0 {
+                    *radiance
+                } else {
+                    self.accumulation[acc_idx] * (1.0 - weight) + *radiance * weight
+                };
+                self.accumulation[acc_idx] = mean;
+                self.set_pixel(px, py, mean);
+            }
+        }
+
+        pub fn to_rgb(&self) -> Vec<u8> {
+            self.pixels
+                .chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0]])
+                .collect()
+        }
+
+        // The format is picked from the file extension (.png or .ppm)
+        pub fn save(&self, path: &Path) -> Result<(), String> {
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_ascii_lowercase());
+            match extension.as_deref() {
+                Some("png") => self.write_png(path),
+                Some("ppm") => self.write_ppm(path),
+                _ => Err(format!("{}: unsupported image format", path.display())),
+            }
+        }
+
+        pub fn write_png(&self, path: &Path) -> Result<(), String> {
+            let file = File::create(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+            encoder.set_color(png::ColorType::RGB);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder
+                .write_header()
+                .and_then(|mut writer| writer.write_image_data(&self.to_rgb()))
+                .map_err(|err| format!("{}: {}", path.display(), err))
+        }
+
+        pub fn write_ppm(&self, path: &Path) -> Result<(), String> {
+            let file = File::create(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            let mut writer = BufWriter::new(file);
+            write!(writer, "P6\n{} {}\n255\n", self.width, self.height)
+                .and_then(|_| writer.write_all(&self.to_rgb()))
+                .and_then(|_| writer.flush())
+                .map_err(|err| format!("{}: {}", path.display(), err))
+        }
     }
 
     pub struct Surface {
@@ -158,20 +287,74 @@ pub mod types {
         }
     }
 
-    use std::sync::{Arc, Mutex};
+    use std::fs::File;
+    use std::io::{BufWriter, Write};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex, OnceLock};
+
+    const TILE_SIZE: usize = 32;
+
+    fn worker_count() -> u32 {
+        std::thread::available_parallelism()
+            .map(|count| count.get() as u32)
+            .unwrap_or(1)
+    }
 
     pub struct Camera {
-        fov: f32,
+        projection: Projection,
+        // Width over height of the image plane, `None` follows the surface
+        aspect: Option<f32>,
         pub origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        world_up: Vector3<f32>,
+        // Degrees, clockwise around the view direction
+        roll: f32,
+        // Orthonormal basis derived from direction, world_up and roll
+        forward: Vector3<f32>,
+        right: Vector3<f32>,
+        up: Vector3<f32>,
         render_target: Option<Arc<Surface>>,
+        samples_per_pixel: u32,
+        sample_pattern: SamplePattern,
+        filter: Filter,
+        integrator: Box<dyn Integrator>,
+        // Kept alive between frames, one worker per core
+        pool: Mutex<scoped_threadpool::Pool>,
+        // Thin lens radius, 0 is a pinhole
+        aperture: f32,
+        // Distance along the view direction that is in focus
+        focus_distance: f32,
+        // When set, frames are averaged until the camera changes
+        progressive: bool,
+        frame: AtomicU32,
     }
 
     impl Camera {
         pub fn new(origin: Vector3<f32>, fov: f32) -> Self {
             Self {
-                fov,
+                projection: Projection::Perspective {
+                    fov,
+                    fov_axis: FovAxis::Vertical,
+                },
+                aspect: None,
                 origin,
+                direction: -Vector3::unit_z(),
+                world_up: Vector3::unit_y(),
+                roll: 0.0,
+                forward: -Vector3::unit_z(),
+                right: Vector3::unit_x(),
+                up: Vector3::unit_y(),
                 render_target: None,
+                samples_per_pixel: 1,
+                sample_pattern: SamplePattern::Stratified,
+                filter: Filter::Box,
+                integrator: Box::new(Whitted::new(4)),
+                pool: Mutex::new(scoped_threadpool::Pool::new(worker_count())),
+                aperture: 0.0,
+                focus_distance: 1.0,
+                progressive: false,
+                frame: AtomicU32::new(0),
             }
         }
 
@@ -179,40 +362,245 @@ pub mod types {
             self.render_target = Some(surface);
         }
 
+        pub fn look_at(&mut self, target: Vector3<f32>, up: Vector3<f32>) -> Result<(), String> {
+            self.set_direction(target - self.origin, up)
+        }
+
+        pub fn set_direction(&mut self, direction: Vector3<f32>, up: Vector3<f32>) -> Result<(), String> {
+            if direction.magnitude2() < 1e-12 || up.magnitude2() < 1e-12 {
+                return Err(String::from("Camera direction and up vector must be non-zero"));
+            }
+            self.direction = direction.normalize();
+            self.world_up = up.normalize();
+            self.update_basis();
+            Ok(())
+        }
+
+        // `offset` is in camera space: x to the right of the image, y up it
+        // and z forward
+        pub fn translate_local(&mut self, offset: Vector3<f32>) {
+            self.origin += self.right * offset.x + self.up * offset.y + self.forward * offset.z;
+            self.reset_accumulation();
+        }
+
+        // Yaw turns around the world up vector, pitch stops short of it but
+        // can always turn away from it
+        pub fn turn(&mut self, yaw: f32, pitch: f32) {
+            let yawed = Matrix3::from_axis_angle(self.world_up, Deg(-yaw)) * self.direction;
+            let right = self.right_of(yawed);
+            let pitched = Matrix3::from_axis_angle(right, Deg(pitch)) * yawed;
+            let steepness = |v: Vector3<f32>| v.dot(self.world_up).abs();
+            self.direction = if steepness(pitched) < 0.995 || steepness(pitched) < steepness(yawed) {
+                pitched.normalize()
+            } else {
+                yawed.normalize()
+            };
+            self.update_basis();
+        }
+
+        pub fn set_roll(&mut self, roll: f32) {
+            self.roll = roll;
+            self.update_basis();
+        }
+
+        pub fn set_projection(&mut self, projection: Projection) {
+            self.projection = projection;
+            self.reset_accumulation();
+        }
+
+        pub fn zoom(&mut self, factor: f32) {
+            self.projection.zoom(factor);
+            self.reset_accumulation();
+        }
+
+        pub fn set_depth_of_field(&mut self, aperture: f32, focus_distance: f32) -> Result<(), String> {
+            if aperture < 0.0 || focus_distance <= 0.0 {
+                return Err(String::from("Aperture must not be negative and focus distance must be positive"));
+            }
+            self.aperture = aperture;
+            self.focus_distance = focus_distance;
+            self.reset_accumulation();
+            Ok(())
+        }
+
+        pub fn set_progressive(&mut self, progressive: bool) {
+            self.progressive = progressive;
+            self.reset_accumulation();
+        }
+
+        pub fn reset_accumulation(&self) {
+            self.frame.store(0, Ordering::Relaxed);
+        }
+
+        // Number of frames averaged into the surface so far
+        pub fn accumulated_frames(&self) -> u32 {
+            self.frame.load(Ordering::Relaxed)
+        }
+
+        pub fn set_aspect(&mut self, aspect: Option<f32>) {
+            self.aspect = aspect;
+            self.reset_accumulation();
+        }
+
+        // Unit vector to the right of `forward` before any roll
+        fn right_of(&self, forward: Vector3<f32>) -> Vector3<f32> {
+            // Looking straight along the up vector, any perpendicular will do
+            let up_hint = if forward.cross(self.world_up).magnitude2() < 1e-8 {
+                if forward.x.abs() < 0.9 { Vector3::unit_x() } else { Vector3::unit_z() }
+            } else {
+                self.world_up
+            };
+            forward.cross(up_hint).normalize()
+        }
+
+        fn update_basis(&mut self) {
+            let forward = self.direction;
+            let right = self.right_of(forward);
+            let up = right.cross(forward);
+
+            let (sin_roll, cos_roll) = self.roll.to_radians().sin_cos();
+            self.forward = forward;
+            self.right = right * cos_roll - up * sin_roll;
+            self.up = up * cos_roll + right * sin_roll;
+            self.reset_accumulation();
+        }
+
+        pub fn set_samples_per_pixel(&mut self, samples_per_pixel: u32) {
+            self.samples_per_pixel = samples_per_pixel.max(1);
+        }
+
+        pub fn set_sample_pattern(&mut self, sample_pattern: SamplePattern) {
+            self.sample_pattern = sample_pattern;
+        }
+
+        pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+            self.integrator = integrator;
+            self.reset_accumulation();
+        }
+
+        pub fn set_filter(&mut self, filter: Filter) {
+            self.filter = filter;
+        }
+
+        // `x` and `y` are continuous film coordinates, pixel centers sit at
+        // half-integer positions
+        // `lens` is a point in [0, 1)^2, mapped onto the aperture disk.
+        // `None` where the projection doesn't cover the image.
+        fn primary_ray(&self, x: f32, y: f32, width: usize, height: usize, lens: (f32, f32)) -> Option<Ray> {
+            let aspect = self.aspect.unwrap_or(width as f32 / height as f32);
+            let screen_x = 2_f32 * x / (width as f32) - 1_f32;
+            let screen_y = -(2_f32 * y / (height as f32) - 1_f32);
+
+            let (offset, dir) = self.projection.generate(screen_x, screen_y, aspect)?;
+            let to_world = |v: Vector3<f32>| self.right * v.x + self.up * v.y + self.forward * v.z;
+            let ray_orig = self.origin + to_world(offset);
+            let ray_dir = to_world(dir);
+
+            // Directions that never reach the focal plane stay sharp
+            if self.aperture <= 0.0 || dir.z <= 1e-4 {
+                return Ray::new(ray_orig, ray_dir.normalize()).ok();
+            }
+
+            // Thin lens: every ray through this pixel meets on the focal plane
+            let focus_point = ray_orig + ray_dir * (self.focus_distance / dir.z);
+            let (lens_x, lens_y) = concentric_disk(lens.0, lens.1);
+            let lens_point = ray_orig + (self.right * lens_x + self.up * lens_y) * self.aperture;
+
+            Ray::new(lens_point, (focus_point - lens_point).normalize()).ok()
+        }
+
+        fn render_pixel(&self, x: usize, y: usize, width: usize, height: usize, scene: &Scene, frame: u32) -> Radiance {
+            let mut rng = Rng::for_pixel(x, y, frame);
+            let center_x = x as f32 + 0.5;
+            let center_y = y as f32 + 0.5;
+
+            // Progressive frames have to jitter or they would all be the same
+            if self.samples_per_pixel == 1 && !self.progressive && self.aperture <= 0.0 {
+                return match self.primary_ray(center_x, center_y, width, height, (0.5, 0.5)) {
+                    Some(ray) => self.integrator.radiance(&ray, scene, &mut rng),
+                    None => Radiance::black(),
+                };
+            }
+
+            let radius = self.filter.radius();
+            let film_samples = self.sample_pattern.generate(self.samples_per_pixel, &mut rng);
+            // Shuffled so lens positions aren't correlated with film positions
+            let mut lens_samples = self.sample_pattern.generate(self.samples_per_pixel, &mut rng);
+            for i in (1..lens_samples.len()).rev() {
+                let j = (rng.next_u32() as usize) % (i + 1);
+                lens_samples.swap(i, j);
+            }
+
+            let mut weighted_sum = Radiance::black();
+            let mut unweighted_sum = Radiance::black();
+            let mut weight_sum = 0.0;
+            for ((u, v), lens) in film_samples.into_iter().zip(lens_samples) {
+                let dx = (2.0 * u - 1.0) * radius;
+                let dy = (2.0 * v - 1.0) * radius;
+                let radiance = match self.primary_ray(center_x + dx, center_y + dy, width, height, lens) {
+                    Some(ray) => self.integrator.radiance(&ray, scene, &mut rng),
+                    None => Radiance::black(),
+                };
+                let weight = self.filter.evaluate(dx, dy);
+                weighted_sum += radiance * weight;
+                unweighted_sum += radiance;
+                weight_sum += weight;
+            }
+
+            // Negative lobes (Mitchell) can cancel out almost entirely
+            if weight_sum.abs() > 1e-4 {
+                weighted_sum / weight_sum
+            } else {
+                unweighted_sum / self.samples_per_pixel as f32
+            }
+        }
+
         pub fn render_scene(&self, scene: &Scene) -> Result<(), String> {
             if let Some(render_target) = &self.render_target {
                 let width = render_target.width;
                 let height = render_target.height;
-                let mut pool = scoped_threadpool::Pool::new(256);
+                let tiles_x = width.div_ceil(TILE_SIZE);
+                let tiles_y = height.div_ceil(TILE_SIZE);
+                let tile_count = tiles_x * tiles_y;
+                let next_tile = AtomicUsize::new(0);
+                let frame = if self.progressive {
+                    self.frame.fetch_add(1, Ordering::Relaxed)
+                } else {
+                    0
+                };
+
+                let mut pool = self.pool.lock().unwrap();
+                let workers = pool.thread_count();
                 pool.scoped(|scoped| {
-                    (0..width).for_each(|x| {
-                        (0..height).for_each(|y| {
-                            scoped.execute(move || {
-                                let mut ray_dir = Vector3::zero();
-
-                                ray_dir.x = (2_f32 * ((x as f32) + 0.5_f32) / (width as f32)
-                                    - 1_f32)
-                                    * (self.fov.to_radians() / 2.0).tan()
-                                    * (width as f32)
-                                    / (height as f32);
-
-                                ray_dir.y = -(2_f32 * ((y as f32) + 0.5) / (height as f32) - 1_f32)
-                                    * (self.fov.to_radians() / 2_f32).tan();
+                    for _ in 0..workers {
+                        let next_tile = &next_tile;
+                        scoped.execute(move || {
+                            let mut buffer = Vec::with_capacity(TILE_SIZE * TILE_SIZE);
+                            loop {
+                                let tile = next_tile.fetch_add(1, Ordering::Relaxed);
+                                if tile >= tile_count {
+                                    break;
+                                }
+                                let x0 = (tile % tiles_x) * TILE_SIZE;
+                                let y0 = (tile / tiles_x) * TILE_SIZE;
+                                let x1 = (x0 + TILE_SIZE).min(width);
+                                let y1 = (y0 + TILE_SIZE).min(height);
+
+                                buffer.clear();
+                                for y in y0..y1 {
+                                    for x in x0..x1 {
+                                        buffer.push(self.render_pixel(x, y, width, height, scene, frame));
+                                    }
+                                }
 
-                                ray_dir.z = -1_f32;
-
-                                let ray: Ray = Ray::new(self.origin, ray_dir.normalize()).unwrap();
-
-                                let depth = 4;
-                                let pixel_color = self.cast_ray(&ray, scene, depth);
                                 render_target
                                     .pixels
                                     .lock()
                                     .unwrap()
-                                    .set_pixel(x, y, pixel_color)
-                            });
+                                    .accumulate_tile(x0, y0, x1 - x0, &buffer, frame);
+                            }
                         });
-                    });
+                    }
                 });
                 return Ok(());
             } else {
@@ -220,95 +608,6 @@ pub mod types {
             }
         }
 
-        pub fn cast_ray(&self, ray: &Ray, scene: &crate::tracer::types::Scene, depth: u32) -> Color {
-            if depth > 0 {
-                if let Some(hit_data) = scene.ray_intersect(ray) {
-                    let view_v3 = ray.direction;
-                    let mut diffuse_light_intensity = 0.0;
-                    let mut specular_light_intensity = 0.0;
-                    let view_reflect_v3 = super::ops::reflect(view_v3, hit_data.normal);
-                    let reflect_orig: Vector3<f32> =
-                        if dot(view_reflect_v3, hit_data.normal) < 0_f32 {
-                            hit_data.hit - hit_data.normal * 1e-3
-                        } else {
-                            hit_data.hit + hit_data.normal * 1e-3
-                        };
-
-                    let refract_dir = super::ops::refract(
-                        view_v3,
-                        hit_data.normal,
-                        hit_data.material.refractive_index,
-                    )
-                    .normalize();
-                    let refract_orig = if refract_dir.dot(hit_data.normal) < 0_f32 {
-                        hit_data.hit - hit_data.normal * 1e-3
-                    } else {
-                        hit_data.hit + hit_data.normal * 1e-3
-                    };
-                    let refract_color = self.cast_ray(
-                        &Ray::new(refract_orig, refract_dir).unwrap(),
-                        scene,
-                        depth - 1,
-                    );
-
-                    let reflect_color = self.cast_ray(
-                        &Ray::new(reflect_orig, view_reflect_v3).unwrap(),
-                        scene,
-                        depth - 1,
-                    );
-                    for light in scene.lights.iter() {
-                        let light_dir = light.get_direction(hit_data.hit);
-                        let light_dot_norm = light_dir.dot(hit_data.normal);
-
-                        let light_distance = (light.get_position() - hit_data.hit).magnitude();
-
-                        // Shadows
-                        let shadow_test_orig = if light_dot_norm < 0_f32 {
-                            hit_data.hit - hit_data.normal * 1e-3
-                        } else {
-                            hit_data.hit + hit_data.normal * 1e-3
-                        };
-                        let shadow_ray = Ray::new(shadow_test_orig, light_dir).unwrap();
-                        if let Some(shadow_hit) = scene.ray_intersect(&shadow_ray) {
-                            if shadow_hit.distance < light_distance {
-                                continue;
-                            }
-                        }
-                        // End of: Shadows
-
-                        let light_reflect_v3 = super::ops::reflect(light_dir, hit_data.normal);
-
-                        diffuse_light_intensity +=
-                            light.get_intensity() * (0.0_f32).max(light_dot_norm);
-                        specular_light_intensity += light.get_intensity()
-                            * light_reflect_v3
-                                .dot(view_v3)
-                                .max(0.0_f32)
-                                .powf(hit_data.material.specular_exp)
-                    }
-                    // diffuse part
-                    let mut final_color = hit_data.material.base_color
-                        * diffuse_light_intensity
-                        * hit_data.material.diffuse_reflection;
-
-                    // specular part
-                    final_color = final_color
-                        + (Color::RGB(255, 255, 255)
-                            * specular_light_intensity
-                            * hit_data.material.specular_reflection);
-
-                    // reflective part
-                    final_color = final_color + (reflect_color * hit_data.material.reflectiveness);
-
-                    // refractive part
-                    final_color = final_color + (refract_color * hit_data.material.refractiveness);
-
-                    return final_color;
-                }
-            }
-            // Color::default()
-            Color::RGB(155, 200, 100)
-        }
     }
 }
 
@@ -320,11 +619,54 @@ pub mod ops {
         incident - normal * 2.0 * incident.dot(normal)
     }
 
+    // Moves a point off the surface to the side `direction` leaves through,
+    // so rays don't hit the surface they start on
+    pub fn offset_origin(point: Vector3<f32>, normal: Vector3<f32>, direction: Vector3<f32>) -> Vector3<f32> {
+        if direction.dot(normal) < 0_f32 {
+            point - normal * 1e-3
+        } else {
+            point + normal * 1e-3
+        }
+    }
+
+    // Two unit vectors that form a right-handed basis with the unit `axis`
+    pub fn orthonormal_basis(axis: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+        // Duff et al., "Building an Orthonormal Basis, Revisited"
+        let sign = 1_f32.copysign(axis.z);
+        let a = -1.0 / (sign + axis.z);
+        let b = axis.x * axis.y * a;
+        let tangent = Vector3::new(1.0 + sign * axis.x * axis.x * a, sign * b, -sign * axis.x);
+        let bitangent = Vector3::new(b, sign + axis.y * axis.y * a, -axis.y);
+        (tangent, bitangent)
+    }
+
+    // Orthonormal tangent and bitangent around the unit `normal`, following
+    // the surface's `tangent` and `bitangent` so tangent space lines up with
+    // the UVs. Falls back to an arbitrary basis where those degenerate.
+    pub fn tangent_frame(normal: Vector3<f32>, tangent: Vector3<f32>, bitangent: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+        let t = tangent - normal * normal.dot(tangent);
+        if t.magnitude2() < 1e-12 {
+            return orthonormal_basis(normal);
+        }
+        let t = t.normalize();
+        // Mirrored UVs keep their handedness
+        let b = normal.cross(t);
+        if b.dot(bitangent) < 0.0 { (t, -b) } else { (t, b) }
+    }
+
+    // Rotates `local`, given around +Z, so that +Z lines up with `axis`
+    pub fn to_world(local: Vector3<f32>, axis: Vector3<f32>) -> Vector3<f32> {
+        let (tangent, bitangent) = orthonormal_basis(axis);
+        tangent * local.x + bitangent * local.y + axis * local.z
+    }
+
+    // Direction of the transmitted ray, None on total internal reflection.
+    // Rays hitting the back of `normal` are taken to leave the medium.
     pub fn refract(
         incident: Vector3<f32>,
         normal: Vector3<f32>,
         refractive_index: f32,
-    ) -> Vector3<f32> {
+    ) -> Option<Vector3<f32>> {
         let mut cos_theta_1 = -incident.dot(normal).max(-1.0).min(1.0); // Assuming both vectors are normalized
         let mut n1: f32 = 1.0; // Default 'n' in vacuum
         let mut n2: f32 = refractive_index;
@@ -340,9 +682,31 @@ pub mod ops {
         let cos_theta_2_sq: f32 = 1.0 - r.powi(2) * (1.0 - cos_theta_1.powi(2));
 
         if cos_theta_2_sq < 0.0 {
-            Vector3::unit_x()
+            None
         } else {
-            incident * r + n * (r * cos_theta_1 - cos_theta_2_sq.sqrt())
+            Some(incident * r + n * (r * cos_theta_1 - cos_theta_2_sq.sqrt()))
+        }
+    }
+
+    // Share of light a smooth dielectric reflects, from the exact Fresnel
+    // equations for unpolarized light. Sides work as in `refract`, total
+    // internal reflection gives 1.
+    pub fn fresnel(incident: Vector3<f32>, normal: Vector3<f32>, refractive_index: f32) -> f32 {
+        let mut cos_i = -incident.dot(normal).clamp(-1.0, 1.0);
+        let mut n1: f32 = 1.0;
+        let mut n2: f32 = refractive_index;
+        if cos_i < 0.0 {
+            std::mem::swap(&mut n1, &mut n2);
+            cos_i = -cos_i;
+        }
+
+        let sin_t_sq = (n1 / n2).powi(2) * (1.0 - cos_i * cos_i);
+        if sin_t_sq >= 1.0 {
+            return 1.0;
         }
+        let cos_t = (1.0 - sin_t_sq).sqrt();
+        let rs = (n1 * cos_i - n2 * cos_t) / (n1 * cos_i + n2 * cos_t);
+        let rp = (n2 * cos_i - n1 * cos_t) / (n2 * cos_i + n1 * cos_t);
+        (rs * rs + rp * rp) / 2.0
     }
 }
@@ -0,0 +1,84 @@
+use cgmath::Vector3;
+
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug)]
+pub enum FovAxis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Projection {
+    // Pinhole, `fov` in degrees along `fov_axis`
+    Perspective { fov: f32, fov_axis: FovAxis },
+    // Parallel rays, `height` is the vertical extent of the view in world units
+    Orthographic { height: f32 },
+    // Equidistant fisheye, the angle off the view direction grows linearly
+    // with the distance from the image center. `fov` in degrees spans the
+    // shorter side of the image, corners outside the image circle stay black.
+    Fisheye { fov: f32 },
+    // Full 360 x 180 degree panorama, longitude along x and latitude along y
+    Equirectangular,
+}
+
+impl Projection {
+    // `x` and `y` are in [-1, 1] with y pointing up and `aspect` is width over
+    // height. Returns the ray origin offset and direction in camera space (x
+    // right, y up, z forward). Perspective directions have z = 1, so they
+    // reach the focal plane at the focus distance.
+    pub fn generate(&self, x: f32, y: f32, aspect: f32) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        match *self {
+            Projection::Perspective { fov, fov_axis } => {
+                let tan_half_fov = (fov.to_radians() / 2_f32).tan();
+                let (tan_half_x, tan_half_y) = match fov_axis {
+                    FovAxis::Vertical => (tan_half_fov * aspect, tan_half_fov),
+                    FovAxis::Horizontal => (tan_half_fov, tan_half_fov / aspect),
+                };
+                Some((Vector3::new(0.0, 0.0, 0.0), Vector3::new(x * tan_half_x, y * tan_half_y, 1.0)))
+            }
+            Projection::Orthographic { height } => {
+                let half_height = height / 2.0;
+                Some((
+                    Vector3::new(x * half_height * aspect, y * half_height, 0.0),
+                    Vector3::new(0.0, 0.0, 1.0),
+                ))
+            }
+            Projection::Fisheye { fov } => {
+                let (px, py) = if aspect >= 1.0 { (x * aspect, y) } else { (x, y / aspect) };
+                let r = (px * px + py * py).sqrt();
+                if r > 1.0 {
+                    return None;
+                }
+                let theta = r * fov.to_radians() / 2.0;
+                let phi = py.atan2(px);
+                Some((
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()),
+                ))
+            }
+            Projection::Equirectangular => {
+                let longitude = x * PI;
+                let latitude = y * PI / 2.0;
+                Some((
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(
+                        latitude.cos() * longitude.sin(),
+                        latitude.sin(),
+                        latitude.cos() * longitude.cos(),
+                    ),
+                ))
+            }
+        }
+    }
+
+    // Scales the field of view, factors below 1 zoom in
+    pub fn zoom(&mut self, factor: f32) {
+        match self {
+            Projection::Perspective { fov, .. } => *fov = (*fov * factor).clamp(1.0, 179.0),
+            Projection::Orthographic { height } => *height = (*height * factor).max(1e-3),
+            Projection::Fisheye { fov } => *fov = (*fov * factor).clamp(1.0, 360.0),
+            Projection::Equirectangular => {}
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use cgmath::Vector3;
+
+// Small PCG32 generator. Renders seed one per pixel so results don't depend
+// on how work is split between threads.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+    const INCREMENT: u64 = 1442695040888963407;
+
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn for_pixel(x: usize, y: usize, seed: u32) -> Self {
+        let mut h = (x as u64) << 32 | (y as u64);
+        h ^= (seed as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        // splitmix64 finalizer to spread neighbouring pixels apart
+        h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self::new(h ^ (h >> 31))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULTIPLIER).wrapping_add(Self::INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+}
+
+// Shirley's concentric mapping from [0, 1)^2 onto the unit disk, keeps
+// strata intact
+pub fn concentric_disk(u: f32, v: f32) -> (f32, f32) {
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, std::f32::consts::FRAC_PI_4 * (b / a))
+    } else {
+        (b, std::f32::consts::FRAC_PI_2 - std::f32::consts::FRAC_PI_4 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+// Cosine-weighted direction around +Z, pdf is cos(theta) / pi
+pub fn cosine_hemisphere(u: f32, v: f32) -> Vector3<f32> {
+    let (x, y) = concentric_disk(u, v);
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+    Vector3::new(x, y, z)
+}
+
+// Uniform direction around +Z, pdf is 1 / (2 pi)
+pub fn uniform_hemisphere(u: f32, v: f32) -> Vector3<f32> {
+    let sin_theta = (1.0 - u * u).max(0.0).sqrt();
+    let phi = 2.0 * std::f32::consts::PI * v;
+    Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), u)
+}
+
+// Direction around +Z distributed as cos(alpha)^exponent, pdf is
+// (exponent + 1) / (2 pi) * cos(alpha)^exponent
+pub fn phong_lobe(u: f32, v: f32, exponent: f32) -> Vector3<f32> {
+    let cos_alpha = u.powf(1.0 / (exponent + 1.0));
+    let sin_alpha = (1.0 - cos_alpha * cos_alpha).max(0.0).sqrt();
+    let phi = 2.0 * std::f32::consts::PI * v;
+    Vector3::new(sin_alpha * phi.cos(), sin_alpha * phi.sin(), cos_alpha)
+}
+
+// GGX distributed microfacet normal around +Z with roughness `alpha`, pdf is
+// D(h) * cos(theta_h)
+pub fn ggx_half_vector(u: f32, v: f32, alpha: f32) -> Vector3<f32> {
+    let a2 = alpha * alpha;
+    let cos_theta = ((1.0 - u) / (1.0 + (a2 - 1.0) * u)).max(0.0).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * std::f32::consts::PI * v;
+    Vector3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum SamplePattern {
+    // Uniformly distributed over the filter footprint
+    Jittered,
+    // One sample per cell of a jittered n x n grid, where n is the largest
+    // square that fits in the sample count. Leftover samples are jittered.
+    Stratified,
+}
+
+impl SamplePattern {
+    // Offsets in [0, 1)^2, to be scaled onto the filter footprint
+    pub fn generate(&self, count: u32, rng: &mut Rng) -> Vec<(f32, f32)> {
+        let count = count as usize;
+        let mut samples = Vec::with_capacity(count);
+        if let SamplePattern::Stratified = self {
+            let n = (count as f32).sqrt() as usize;
+            for j in 0..n {
+                for i in 0..n {
+                    samples.push((
+                        (i as f32 + rng.next_f32()) / n as f32,
+                        (j as f32 + rng.next_f32()) / n as f32,
+                    ));
+                }
+            }
+        }
+        while samples.len() < count {
+            samples.push((rng.next_f32(), rng.next_f32()));
+        }
+        samples
+    }
+}
+
+// Pixel reconstruction filters, evaluated at an offset in pixels from the
+// pixel center
+#[derive(Clone, Copy, Debug)]
+pub enum Filter {
+    Box,
+    Tent,
+    Gaussian,
+    // Mitchell-Netravali with B = C = 1/3
+    Mitchell,
+}
+
+impl Filter {
+    pub fn radius(&self) -> f32 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    pub fn evaluate(&self, x: f32, y: f32) -> f32 {
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => (1.0 - x.abs()).max(0.0) * (1.0 - y.abs()).max(0.0),
+            Filter::Gaussian => gaussian_1d(x, self.radius()) * gaussian_1d(y, self.radius()),
+            Filter::Mitchell => mitchell_1d(x / self.radius()) * mitchell_1d(y / self.radius()),
+        }
+    }
+}
+
+fn gaussian_1d(x: f32, radius: f32) -> f32 {
+    const ALPHA: f32 = 2.0;
+    ((-ALPHA * x * x).exp() - (-ALPHA * radius * radius).exp()).max(0.0)
+}
+
+// `x` normalized to [-1, 1] over the filter footprint
+fn mitchell_1d(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+    let x = (2.0 * x).abs();
+    if x > 2.0 {
+        0.0
+    } else if x > 1.0 {
+        ((-B - 6.0 * C) * x.powi(3) + (6.0 * B + 30.0 * C) * x.powi(2) + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+    } else {
+        ((12.0 - 9.0 * B - 6.0 * C) * x.powi(3) + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2) + (6.0 - 2.0 * B))
+            / 6.0
+    }
+}
@@ -0,0 +1,33 @@
+use cgmath::{Vector2, Vector3};
+
+use super::{Texture, TextureSpace};
+use crate::tracer::types::Radiance;
+
+// Alternating cubes of two colors, `scale` cells per unit
+pub struct Checker {
+    even: Radiance,
+    odd: Radiance,
+    scale: f32,
+    space: TextureSpace,
+}
+
+impl Checker {
+    pub fn new(even: Radiance, odd: Radiance, scale: f32, space: TextureSpace) -> Self {
+        Self {
+            even,
+            odd,
+            scale,
+            space,
+        }
+    }
+}
+
+impl Texture for Checker {
+    fn sample(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance {
+        // Nudged so surfaces lying exactly on a cell border (a floor at y = 0)
+        // don't flicker between both colors
+        let p = self.space.point(uv, point) * self.scale + Vector3::new(1e-3, 1e-3, 1e-3);
+        let parity = (p.x.floor() + p.y.floor() + p.z.floor()) as i64;
+        if parity.rem_euclid(2) == 0 { self.even } else { self.odd }
+    }
+}
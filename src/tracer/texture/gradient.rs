@@ -0,0 +1,37 @@
+use cgmath::prelude::*;
+use cgmath::{Vector2, Vector3};
+
+use super::{lerp, Texture, TextureSpace};
+use crate::tracer::types::Radiance;
+
+// Linear blend from `from` at `start` to `to` at `end`, constant beyond them
+pub struct Gradient {
+    start: Vector3<f32>,
+    end: Vector3<f32>,
+    from: Radiance,
+    to: Radiance,
+    space: TextureSpace,
+}
+
+impl Gradient {
+    pub fn new(start: Vector3<f32>, end: Vector3<f32>, from: Radiance, to: Radiance, space: TextureSpace) -> Result<Self, String> {
+        if (end - start).magnitude2() < 1e-12 {
+            return Err(String::from("Gradient start and end must differ"));
+        }
+        Ok(Self {
+            start,
+            end,
+            from,
+            to,
+            space,
+        })
+    }
+}
+
+impl Texture for Gradient {
+    fn sample(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance {
+        let axis = self.end - self.start;
+        let t = (self.space.point(uv, point) - self.start).dot(axis) / axis.magnitude2();
+        lerp(self.from, self.to, t.clamp(0.0, 1.0))
+    }
+}
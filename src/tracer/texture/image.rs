@@ -0,0 +1,162 @@
+use cgmath::{Vector2, Vector3};
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::Texture;
+use crate::tracer::types::{Color, Radiance};
+
+// What happens to UVs outside [0, 1)
+#[derive(Clone, Copy, Debug)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+impl WrapMode {
+    fn apply(&self, i: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => i.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let m = i.rem_euclid(2 * size);
+                if m >= size { 2 * size - 1 - m } else { m }
+            }
+            WrapMode::ClampToEdge => i.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+// Bilinearly filtered image, (0, 0) is the bottom left corner
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // Linear, row by row starting at the top
+    texels: Vec<Radiance>,
+    wrap: WrapMode,
+    // Repetitions of the image per unit of UV
+    scale: Vector2<f32>,
+}
+
+impl ImageTexture {
+    pub fn new(width: usize, height: usize, texels: Vec<Radiance>) -> Result<Self, String> {
+        if width == 0 || height == 0 || texels.len() != width * height {
+            return Err(format!("Expected {} texels for a {}x{} image, got {}", width * height, width, height, texels.len()));
+        }
+        Ok(Self {
+            width,
+            height,
+            texels,
+            wrap: WrapMode::Repeat,
+            scale: Vector2::new(1.0, 1.0),
+        })
+    }
+
+    // PNG or JPEG depending on the extension, 8-bit colors are taken as sRGB
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Self::read(path.as_ref(), true)
+    }
+
+    // For images holding data rather than colors, like normal or bump maps
+    pub fn load_linear<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        Self::read(path.as_ref(), false)
+    }
+
+    fn read(path: &Path, srgb: bool) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let extension = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_ascii_lowercase());
+        let (width, height, texels) = match extension.as_deref() {
+            Some("png") => read_png(file, srgb),
+            Some("jpg") | Some("jpeg") => read_jpeg(file, srgb),
+            _ => Err(String::from("Unsupported image format, expected .png, .jpg or .jpeg")),
+        }
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+        Self::new(width, height, texels)
+    }
+
+    pub fn set_wrap(&mut self, wrap: WrapMode) {
+        self.wrap = wrap;
+    }
+
+    pub fn set_scale(&mut self, scale: Vector2<f32>) {
+        self.scale = scale;
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Radiance {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        self.texels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn sample(&self, uv: Vector2<f32>, _point: Vector3<f32>) -> Radiance {
+        // Texel centers sit at half-integer positions
+        let x = uv.x * self.scale.x * self.width as f32 - 0.5;
+        let y = (1.0 - uv.y * self.scale.y) * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+fn to_texels(pixels: &[u8], channels: usize, gray: bool, srgb: bool) -> Vec<Radiance> {
+    pixels
+        .chunks_exact(channels)
+        .map(|p| {
+            let (r, g, b) = if gray { (p[0], p[0], p[0]) } else { (p[0], p[1], p[2]) };
+            if srgb {
+                Radiance::from(Color::RGB(r, g, b))
+            } else {
+                Radiance::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+            }
+        })
+        .collect()
+}
+
+fn read_png(file: File, srgb: bool) -> Result<(usize, usize, Vec<Radiance>), String> {
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    // Palettes and low bit depths become 8-bit gray or RGB, 16 bits get cut to 8
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).map_err(|err| err.to_string())?;
+
+    let (channels, gray) = match info.color_type {
+        png::ColorType::Grayscale => (1, true),
+        png::ColorType::GrayscaleAlpha => (2, true),
+        png::ColorType::RGB => (3, false),
+        png::ColorType::RGBA => (4, false),
+        png::ColorType::Indexed => return Err(String::from("Indexed colors weren't expanded")),
+    };
+    let width = info.width as usize;
+    let height = info.height as usize;
+    // Rows may be padded
+    let texels = pixels
+        .chunks(info.line_size)
+        .take(height)
+        .flat_map(|row| to_texels(&row[..width * channels], channels, gray, srgb))
+        .collect();
+    Ok((width, height, texels))
+}
+
+fn read_jpeg(file: File, srgb: bool) -> Result<(usize, usize, Vec<Radiance>), String> {
+    let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(file));
+    let pixels = decoder.decode().map_err(|err| err.to_string())?;
+    let info = decoder.info().ok_or_else(|| String::from("Missing JPEG header"))?;
+    let (channels, gray) = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => (1, true),
+        jpeg_decoder::PixelFormat::RGB24 => (3, false),
+        jpeg_decoder::PixelFormat::CMYK32 => return Err(String::from("CMYK JPEGs aren't supported")),
+    };
+    Ok((info.width as usize, info.height as usize, to_texels(&pixels, channels, gray, srgb)))
+}
@@ -0,0 +1,41 @@
+mod image;
+mod checker;
+mod gradient;
+mod noise;
+
+pub use self::image::{ImageTexture, WrapMode};
+pub use checker::Checker;
+pub use gradient::Gradient;
+pub use noise::{Marble, NoiseTexture, Perlin, Wood};
+
+use cgmath::{Vector2, Vector3};
+
+use super::types::Radiance;
+
+// Spatially varying surface color
+pub trait Texture: Send + Sync {
+    // `uv` is the surface parameterization of the hit, `point` its position
+    fn sample(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance;
+}
+
+// Where procedural textures are evaluated. `World` carves the pattern out of
+// a solid block, so it lines up across shapes and doesn't stretch. `Uv`
+// follows the surface parameterization with z = 0.
+#[derive(Clone, Copy, Debug)]
+pub enum TextureSpace {
+    World,
+    Uv,
+}
+
+impl TextureSpace {
+    pub fn point(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            TextureSpace::World => point,
+            TextureSpace::Uv => Vector3::new(uv.x, uv.y, 0.0),
+        }
+    }
+}
+
+fn lerp(from: Radiance, to: Radiance, t: f32) -> Radiance {
+    from * (1.0 - t) + to * t
+}
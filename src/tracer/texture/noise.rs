@@ -0,0 +1,227 @@
+use cgmath::{Vector2, Vector3};
+
+use std::f32::consts::PI;
+
+use super::{lerp, Texture, TextureSpace};
+use crate::tracer::sampling::Rng;
+use crate::tracer::types::Radiance;
+
+// Ken Perlin's improved gradient noise, with the permutation shuffled from a
+// seed so different textures don't share the same pattern
+pub struct Perlin {
+    permutation: Vec<u8>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut permutation: Vec<u8> = (0..=255).collect();
+        for i in (1..permutation.len()).rev() {
+            let j = (rng.next_u32() as usize) % (i + 1);
+            permutation.swap(i, j);
+        }
+        // Doubled so lookups never need to wrap
+        permutation.extend_from_within(..);
+        Self { permutation }
+    }
+
+    // Roughly in [-1, 1], zero on every integer lattice point
+    pub fn noise(&self, p: Vector3<f32>) -> f32 {
+        let (xf, yf, zf) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let x = (xf as i64).rem_euclid(256) as usize;
+        let y = (yf as i64).rem_euclid(256) as usize;
+        let z = (zf as i64).rem_euclid(256) as usize;
+        let (fx, fy, fz) = (p.x - xf, p.y - yf, p.z - zf);
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+
+        let perm = &self.permutation;
+        let a = perm[x] as usize + y;
+        let aa = perm[a] as usize + z;
+        let ab = perm[a + 1] as usize + z;
+        let b = perm[x + 1] as usize + y;
+        let ba = perm[b] as usize + z;
+        let bb = perm[b + 1] as usize + z;
+
+        lerp_f32(
+            lerp_f32(
+                lerp_f32(grad(perm[aa], fx, fy, fz), grad(perm[ba], fx - 1.0, fy, fz), u),
+                lerp_f32(grad(perm[ab], fx, fy - 1.0, fz), grad(perm[bb], fx - 1.0, fy - 1.0, fz), u),
+                v,
+            ),
+            lerp_f32(
+                lerp_f32(grad(perm[aa + 1], fx, fy, fz - 1.0), grad(perm[ba + 1], fx - 1.0, fy, fz - 1.0), u),
+                lerp_f32(
+                    grad(perm[ab + 1], fx, fy - 1.0, fz - 1.0),
+                    grad(perm[bb + 1], fx - 1.0, fy - 1.0, fz - 1.0),
+                    u,
+                ),
+                v,
+            ),
+            w,
+        )
+    }
+
+    // Sum of absolute octaves, each twice the frequency and half the
+    // amplitude of the last. Normalized to [0, 1].
+    pub fn turbulence(&self, p: Vector3<f32>, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves.max(1) {
+            sum += self.noise(p * frequency).abs() * amplitude;
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum / total
+    }
+
+    // Like `turbulence` but keeps the sign, in [-1, 1]
+    pub fn fractal(&self, p: Vector3<f32>, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves.max(1) {
+            sum += self.noise(p * frequency) * amplitude;
+            total += amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum / total
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp_f32(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Dot product with one of 12 edge directions of a cube picked by `hash`
+fn grad(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+// Blends two colors by noise. `turbulent` sums absolute octaves, which gives
+// creases instead of smooth blobs.
+pub struct NoiseTexture {
+    perlin: Perlin,
+    low: Radiance,
+    high: Radiance,
+    scale: f32,
+    octaves: u32,
+    turbulent: bool,
+    space: TextureSpace,
+}
+
+impl NoiseTexture {
+    pub fn new(perlin: Perlin, low: Radiance, high: Radiance, scale: f32, octaves: u32, turbulent: bool, space: TextureSpace) -> Self {
+        Self {
+            perlin,
+            low,
+            high,
+            scale,
+            octaves,
+            turbulent,
+            space,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn sample(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance {
+        let p = self.space.point(uv, point) * self.scale;
+        let t = if self.turbulent {
+            self.perlin.turbulence(p, self.octaves)
+        } else {
+            0.5 + 0.5 * self.perlin.fractal(p, self.octaves)
+        };
+        lerp(self.low, self.high, t.clamp(0.0, 1.0))
+    }
+}
+
+// Veins running across the X axis, bent by turbulence
+pub struct Marble {
+    perlin: Perlin,
+    base: Radiance,
+    vein: Radiance,
+    scale: f32,
+    // How far turbulence pushes the veins around
+    distortion: f32,
+    octaves: u32,
+    space: TextureSpace,
+}
+
+impl Marble {
+    pub fn new(perlin: Perlin, base: Radiance, vein: Radiance, scale: f32, distortion: f32, octaves: u32, space: TextureSpace) -> Self {
+        Self {
+            perlin,
+            base,
+            vein,
+            scale,
+            distortion,
+            octaves,
+            space,
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn sample(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance {
+        let p = self.space.point(uv, point) * self.scale;
+        let phase = p.x + self.distortion * self.perlin.turbulence(p, self.octaves);
+        // Veins sit where the wave crosses zero, sharpened so they stay thin
+        let t = (1.0 - (PI * phase).sin().abs()).powi(6);
+        lerp(self.base, self.vein, t)
+    }
+}
+
+// Concentric growth rings around the Y axis, wobbled by noise
+pub struct Wood {
+    perlin: Perlin,
+    light: Radiance,
+    dark: Radiance,
+    scale: f32,
+    // Rings per unit of radius
+    rings: f32,
+    distortion: f32,
+    space: TextureSpace,
+}
+
+impl Wood {
+    pub fn new(perlin: Perlin, light: Radiance, dark: Radiance, scale: f32, rings: f32, distortion: f32, space: TextureSpace) -> Self {
+        Self {
+            perlin,
+            light,
+            dark,
+            scale,
+            rings,
+            distortion,
+            space,
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn sample(&self, uv: Vector2<f32>, point: Vector3<f32>) -> Radiance {
+        let p = self.space.point(uv, point) * self.scale;
+        let radius = (p.x * p.x + p.z * p.z).sqrt();
+        let rings = radius * self.rings + self.distortion * self.perlin.noise(p);
+        // Early wood fades into the darker late wood at the end of each ring
+        let t = (rings - rings.floor()).powi(3);
+        lerp(self.light, self.dark, t)
+    }
+}
@@ -0,0 +1,45 @@
+use super::material::Radiance;
+
+// Maps unbounded scene radiance into [0, 1] before it gets sRGB encoded.
+// Whatever is still out of range gets clamped on quantization.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ToneMap {
+    #[default]
+    Clamp,
+    Reinhard,
+    // Reinhard that maps `white` (and anything brighter) to 1
+    ExtendedReinhard { white: f32 },
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(&self, radiance: Radiance) -> Radiance {
+        match *self {
+            ToneMap::Clamp => radiance,
+            ToneMap::Reinhard => scale_luminance(radiance, |l| l / (1.0 + l)),
+            ToneMap::ExtendedReinhard { white } => {
+                let white_sq = (white * white).max(f32::EPSILON);
+                scale_luminance(radiance, |l| l * (1.0 + l / white_sq) / (1.0 + l))
+            }
+            ToneMap::Aces => radiance.map(|x| {
+                let x = x.max(0.0);
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+        }
+    }
+}
+
+// Exposure compensation in stops
+pub fn exposure_scale(ev: f32) -> f32 {
+    2_f32.powf(ev)
+}
+
+// Operators that work on luminance keep the hue of saturated highlights
+fn scale_luminance<F: Fn(f32) -> f32>(radiance: Radiance, curve: F) -> Radiance {
+    let luminance = radiance.luminance();
+    if luminance <= 0.0 {
+        return Radiance::black();
+    }
+    radiance * (curve(luminance) / luminance)
+}
@@ -0,0 +1,85 @@
+use cgmath::Vector3;
+use cgmath::prelude::*;
+
+use crate::tracer::types;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(a: Vector3<f32>, b: Vector3<f32>) -> Self {
+        Self {
+            min: Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points<I: IntoIterator<Item = Vector3<f32>>>(points: I) -> Self {
+        points
+            .into_iter()
+            .fold(Self::empty(), |aabb, point| aabb.grow(point))
+    }
+
+    pub fn grow(&self, point: Vector3<f32>) -> Self {
+        Self {
+            min: Vector3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            max: Vector3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        self.grow(other.min).grow(other.max)
+    }
+
+    pub fn centroid(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        [
+            Vector3::new(self.min.x, self.min.y, self.min.z),
+            Vector3::new(self.max.x, self.min.y, self.min.z),
+            Vector3::new(self.min.x, self.max.y, self.min.z),
+            Vector3::new(self.max.x, self.max.y, self.min.z),
+            Vector3::new(self.min.x, self.min.y, self.max.z),
+            Vector3::new(self.max.x, self.min.y, self.max.z),
+            Vector3::new(self.min.x, self.max.y, self.max.z),
+            Vector3::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let extent = self.max - self.min;
+        if extent.x < 0.0 || extent.y < 0.0 || extent.z < 0.0 {
+            return 0.0;
+        }
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    // Slab test, returns the entry distance if the ray overlaps the box
+    // before `t_max`. `inv_dir` is the per-component reciprocal of the ray
+    // direction so it can be computed once per traversal.
+    pub fn intersect(&self, ray: &types::Ray, inv_dir: Vector3<f32>, t_max: f32) -> Option<f32> {
+        let t0 = (self.min - ray.origin).mul_element_wise(inv_dir);
+        let t1 = (self.max - ray.origin).mul_element_wise(inv_dir);
+
+        let t_near = t0.x.min(t1.x).max(t0.y.min(t1.y)).max(t0.z.min(t1.z)).max(0.0);
+        let t_far = t0.x.max(t1.x).min(t0.y.max(t1.y)).min(t0.z.max(t1.z)).min(t_max);
+
+        if t_near <= t_far {
+            Some(t_near)
+        } else {
+            None
+        }
+    }
+}
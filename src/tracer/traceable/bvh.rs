@@ -0,0 +1,225 @@
+use cgmath::Vector3;
+
+use crate::tracer::types;
+
+use super::Aabb;
+
+const SAH_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f32 = 1.0;
+const INTERSECTION_COST: f32 = 1.0;
+
+enum BvhNodeKind {
+    // Primitives are `indices[start..start + count]`
+    Leaf { start: usize, count: usize },
+    // The first child always directly follows its parent
+    Interior { second_child: usize, axis: usize },
+}
+
+struct BvhNode {
+    bounds: Aabb,
+    kind: BvhNodeKind,
+}
+
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    indices: Vec<usize>,
+}
+
+struct BuildPrimitive {
+    index: usize,
+    bounds: Aabb,
+    centroid: Vector3<f32>,
+}
+
+impl Bvh {
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut primitives: Vec<BuildPrimitive> = bounds
+            .iter()
+            .enumerate()
+            .map(|(index, bounds)| BuildPrimitive {
+                index,
+                bounds: *bounds,
+                centroid: bounds.centroid(),
+            })
+            .collect();
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(bounds.len() * 2),
+            indices: Vec::with_capacity(bounds.len()),
+        };
+        if !primitives.is_empty() {
+            bvh.build_recursive(&mut primitives);
+        }
+        bvh
+    }
+
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    fn build_recursive(&mut self, primitives: &mut [BuildPrimitive]) -> usize {
+        let bounds = primitives
+            .iter()
+            .fold(Aabb::empty(), |aabb, primitive| aabb.union(&primitive.bounds));
+
+        let node_idx = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds,
+            kind: BvhNodeKind::Leaf { start: 0, count: 0 },
+        });
+
+        let split = if primitives.len() > MAX_LEAF_SIZE {
+            Self::find_split(primitives, &bounds)
+        } else {
+            None
+        };
+
+        match split {
+            Some((axis, mid)) => {
+                let (left, right) = primitives.split_at_mut(mid);
+                self.build_recursive(left);
+                let second_child = self.build_recursive(right);
+                self.nodes[node_idx].kind = BvhNodeKind::Interior { second_child, axis };
+            }
+            None => {
+                let start = self.indices.len();
+                self.indices.extend(primitives.iter().map(|primitive| primitive.index));
+                self.nodes[node_idx].kind = BvhNodeKind::Leaf {
+                    start,
+                    count: primitives.len(),
+                };
+            }
+        }
+
+        node_idx
+    }
+
+    // Binned surface area heuristic. Partitions `primitives` in place and
+    // returns the split axis and position, or `None` if a leaf is cheaper.
+    fn find_split(primitives: &mut [BuildPrimitive], bounds: &Aabb) -> Option<(usize, usize)> {
+        let centroid_bounds = Aabb::from_points(primitives.iter().map(|primitive| primitive.centroid));
+        let extent: [f32; 3] = (centroid_bounds.max - centroid_bounds.min).into();
+        let cmin: [f32; 3] = centroid_bounds.min.into();
+
+        let leaf_cost = INTERSECTION_COST * primitives.len() as f32;
+        let parent_area = bounds.surface_area().max(f32::EPSILON);
+
+        let mut best: Option<(usize, usize)> = None;
+        let mut best_cost = f32::MAX;
+        for axis in 0..3 {
+            if extent[axis] <= f32::EPSILON {
+                continue;
+            }
+            let mut bin_bounds = [Aabb::empty(); SAH_BINS];
+            let mut bin_counts = [0_usize; SAH_BINS];
+            for primitive in primitives.iter() {
+                let bin = bin_index(primitive, axis, cmin[axis], extent[axis]);
+                bin_bounds[bin] = bin_bounds[bin].union(&primitive.bounds);
+                bin_counts[bin] += 1;
+            }
+
+            // Sweep from the right to get the cost of every right partition
+            let mut right_area = [0_f32; SAH_BINS];
+            let mut right_count = [0_usize; SAH_BINS];
+            let mut acc_bounds = Aabb::empty();
+            let mut acc_count = 0;
+            for bin in (1..SAH_BINS).rev() {
+                acc_bounds = acc_bounds.union(&bin_bounds[bin]);
+                acc_count += bin_counts[bin];
+                right_area[bin] = acc_bounds.surface_area();
+                right_count[bin] = acc_count;
+            }
+
+            let mut acc_bounds = Aabb::empty();
+            let mut acc_count = 0;
+            for split in 1..SAH_BINS {
+                acc_bounds = acc_bounds.union(&bin_bounds[split - 1]);
+                acc_count += bin_counts[split - 1];
+                if acc_count == 0 || right_count[split] == 0 {
+                    continue;
+                }
+                let cost = TRAVERSAL_COST
+                    + INTERSECTION_COST
+                        * (acc_bounds.surface_area() * acc_count as f32
+                            + right_area[split] * right_count[split] as f32)
+                        / parent_area;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = Some((axis, split));
+                }
+            }
+        }
+
+        let (axis, split) = best?;
+        if best_cost >= leaf_cost && primitives.len() <= MAX_LEAF_SIZE * 4 {
+            return None;
+        }
+
+        let mut mid = 0;
+        for i in 0..primitives.len() {
+            if bin_index(&primitives[i], axis, cmin[axis], extent[axis]) < split {
+                primitives.swap(i, mid);
+                mid += 1;
+            }
+        }
+
+        Some((axis, mid))
+    }
+
+    // Walks the hierarchy front to back, calling `intersect` for every
+    // primitive whose leaf the ray reaches. `intersect` returns the hit
+    // distance along with whatever the caller needs to rebuild the hit.
+    pub fn traverse<T, F>(&self, ray: &types::Ray, mut intersect: F) -> Option<(f32, T)>
+    where
+        F: FnMut(usize) -> Option<(f32, T)>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inv_dir = Vector3::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        let dir_negative = [ray.direction.x < 0.0, ray.direction.y < 0.0, ray.direction.z < 0.0];
+
+        let mut nearest: Option<(f32, T)> = None;
+        let mut nearest_t = f32::MAX;
+        let mut stack: Vec<usize> = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if node.bounds.intersect(ray, inv_dir, nearest_t).is_none() {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { start, count } => {
+                    for &index in &self.indices[start..start + count] {
+                        if let Some((t, data)) = intersect(index) {
+                            if t < nearest_t {
+                                nearest_t = t;
+                                nearest = Some((t, data));
+                            }
+                        }
+                    }
+                }
+                BvhNodeKind::Interior { second_child, axis } => {
+                    // Visit the child closer to the ray origin first
+                    if dir_negative[axis] {
+                        stack.push(node_idx + 1);
+                        stack.push(second_child);
+                    } else {
+                        stack.push(second_child);
+                        stack.push(node_idx + 1);
+                    }
+                }
+            }
+        }
+
+        nearest
+    }
+}
+
+fn bin_index(primitive: &BuildPrimitive, axis: usize, min: f32, extent: f32) -> usize {
+    let c: [f32; 3] = primitive.centroid.into();
+    (((c[axis] - min) / extent) * SAH_BINS as f32).min(SAH_BINS as f32 - 1.0) as usize
+}
@@ -1,8 +1,54 @@
 pub mod shapes;
+mod aabb;
+mod bvh;
+mod transformed;
+
+pub use aabb::Aabb;
+pub use bvh::Bvh;
+pub use transformed::Transformed;
+
+use cgmath::{ Vector2, Vector3 };
+
+use std::sync::Arc;
 
 use crate::tracer::types;
 
 pub trait Traceable {
     fn ray_intersect(&self, ray: &types::Ray) -> Option<types::RayHit>;
+    // `None` for unbounded shapes such as planes
+    fn bounding_box(&self) -> Option<Aabb>;
+}
+
+// Lets one shape sit in the scene and back a light at the same time
+impl<T: Traceable + ?Sized> Traceable for Arc<T> {
+    fn ray_intersect(&self, ray: &types::Ray) -> Option<types::RayHit> {
+        self.as_ref().ray_intersect(ray)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.as_ref().bounding_box()
+    }
+}
+
+// A point picked on a surface
+pub struct SurfaceSample {
+    pub point: Vector3<f32>,
+    // Faces the side the surface emits from
+    pub normal: Vector3<f32>,
+    pub uv: Vector2<f32>,
+    // Probability density per unit of area
+    pub pdf: f32,
 }
 
+// Finite shapes that can pick points spread evenly over their area, which
+// is what emissive objects need to be sampled as lights
+pub trait Sampleable: Traceable {
+    // `u` is a point in [0, 1)^2
+    fn sample_surface(&self, u: (f32, f32)) -> SurfaceSample;
+
+    // Shapes that know which part of them `at` can see may only pick points
+    // there. The density is still per unit of area.
+    fn sample_surface_from(&self, _at: Vector3<f32>, u: (f32, f32)) -> SurfaceSample {
+        self.sample_surface(u)
+    }
+}
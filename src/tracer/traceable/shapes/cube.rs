@@ -1,4 +1,4 @@
-use cgmath::{ Vector3, Point3 };
+use cgmath::{ Vector2, Vector3, Point3 };
 use cgmath::prelude::*;
 
 use std::mem::swap;
@@ -51,6 +51,42 @@ impl Cube {
 
         return normal.normalize();
     }
+
+    // Every face covers the unit square, upright and unmirrored when seen
+    // from outside
+    pub fn face_uv(&self, point: Vector3<f32>, normal: Vector3<f32>) -> Vector2<f32> {
+        let rel = (point - self.vmin).div_element_wise(self.vmax - self.vmin);
+        if normal.x > 0.5 {
+            Vector2::new(1.0 - rel.z, rel.y)
+        } else if normal.x < -0.5 {
+            Vector2::new(rel.z, rel.y)
+        } else if normal.y > 0.5 {
+            Vector2::new(rel.x, 1.0 - rel.z)
+        } else if normal.y < -0.5 {
+            Vector2::new(rel.x, rel.z)
+        } else if normal.z > 0.5 {
+            Vector2::new(rel.x, rel.y)
+        } else {
+            Vector2::new(1.0 - rel.x, rel.y)
+        }
+    }
+
+    // Directions of growing u and v on each face, matching `face_uv`
+    pub fn face_tangents(normal: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+        if normal.x > 0.5 {
+            (-Vector3::unit_z(), Vector3::unit_y())
+        } else if normal.x < -0.5 {
+            (Vector3::unit_z(), Vector3::unit_y())
+        } else if normal.y > 0.5 {
+            (Vector3::unit_x(), -Vector3::unit_z())
+        } else if normal.y < -0.5 {
+            (Vector3::unit_x(), Vector3::unit_z())
+        } else if normal.z > 0.5 {
+            (Vector3::unit_x(), Vector3::unit_y())
+        } else {
+            (-Vector3::unit_x(), Vector3::unit_y())
+        }
+    }
 }
 
 impl traceable::Traceable for Cube {
@@ -96,9 +132,50 @@ impl traceable::Traceable for Cube {
             return None;
         }
         rayhit.normal = self.normal_at(rayhit.hit);
-        rayhit.material = self.material;
+        rayhit.uv = self.face_uv(rayhit.hit, rayhit.normal);
+        let (tangent, bitangent) = Self::face_tangents(rayhit.normal);
+        rayhit.tangent = tangent;
+        rayhit.bitangent = bitangent;
+        rayhit.material = self.material.clone();
 
         return Some(rayhit);
     }
+
+    fn bounding_box(&self) -> Option<traceable::Aabb> {
+        Some(traceable::Aabb::new(self.vmin, self.vmax))
+    }
 }
 
+impl traceable::Sampleable for Cube {
+    fn sample_surface(&self, u: (f32, f32)) -> traceable::SurfaceSample {
+        let lo = Vector3::new(self.vmin.x.min(self.vmax.x), self.vmin.y.min(self.vmax.y), self.vmin.z.min(self.vmax.z));
+        let size = (self.vmax - self.vmin).map(f32::abs);
+        // Two faces per axis, picked by area with `u.0`, which is then
+        // stretched back over [0, 1)
+        let areas = [size.y * size.z, size.x * size.z, size.x * size.y];
+        let total = areas.iter().sum::<f32>();
+        let mut target = u.0 * total;
+        let mut axis = 0;
+        while axis < 2 && target >= areas[axis] {
+            target -= areas[axis];
+            axis += 1;
+        }
+        let t = if areas[axis] > 0.0 { (target / areas[axis]).clamp(0.0, 1.0) } else { 0.0 };
+        let side = if t < 0.5 { -1.0 } else { 1.0 };
+        let a = (2.0 * t) % 1.0;
+        let b = u.1;
+
+        let (point, normal) = match axis {
+            0 => (Vector3::new(if side > 0.0 { size.x } else { 0.0 }, a * size.y, b * size.z), Vector3::unit_x() * side),
+            1 => (Vector3::new(a * size.x, if side > 0.0 { size.y } else { 0.0 }, b * size.z), Vector3::unit_y() * side),
+            _ => (Vector3::new(a * size.x, b * size.y, if side > 0.0 { size.z } else { 0.0 }), Vector3::unit_z() * side),
+        };
+        let point = lo + point;
+        traceable::SurfaceSample {
+            point,
+            normal,
+            uv: self.face_uv(point, normal),
+            pdf: 1.0 / (2.0 * total),
+        }
+    }
+}
@@ -1,8 +1,10 @@
-use cgmath::Vector3;
+use cgmath::{ Vector2, Vector3 };
 use cgmath::dot;
 use cgmath::prelude::*;
 
 use crate::tracer::material;
+use crate::tracer::ops;
+use crate::tracer::sampling::concentric_disk;
 use crate::tracer::types;
 use crate::tracer::traceable;
 
@@ -43,8 +45,37 @@ impl traceable::Traceable for Disk {
             return None;
         }
         rayhit.normal = self.normal;
-        rayhit.material = self.material;
+        // The disk fills the unit square
+        let (tangent, bitangent) = ops::orthonormal_basis(self.normal);
+        let local = (rayhit.hit - self.position) / self.radius;
+        rayhit.uv = Vector2::new(0.5 + 0.5 * local.dot(tangent), 0.5 + 0.5 * local.dot(bitangent));
+        rayhit.tangent = tangent;
+        rayhit.bitangent = bitangent;
+        rayhit.material = self.material.clone();
         return Some(rayhit);
     }
+
+    fn bounding_box(&self) -> Option<traceable::Aabb> {
+        // Extent of the disk's rim along each axis
+        let n = self.normal;
+        let e = Vector3::new(
+            (1.0 - n.x * n.x).max(0.0).sqrt(),
+            (1.0 - n.y * n.y).max(0.0).sqrt(),
+            (1.0 - n.z * n.z).max(0.0).sqrt(),
+        ) * self.radius;
+        Some(traceable::Aabb::new(self.position - e, self.position + e))
+    }
 }
 
+impl traceable::Sampleable for Disk {
+    fn sample_surface(&self, u: (f32, f32)) -> traceable::SurfaceSample {
+        let (x, y) = concentric_disk(u.0, u.1);
+        let (tangent, bitangent) = ops::orthonormal_basis(self.normal);
+        traceable::SurfaceSample {
+            point: self.position + (tangent * x + bitangent * y) * self.radius,
+            normal: self.normal,
+            uv: Vector2::new(0.5 + 0.5 * x, 0.5 + 0.5 * y),
+            pdf: 1.0 / (std::f32::consts::PI * self.radius * self.radius),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use cgmath::{ Vector2, Vector3 };
+use cgmath::prelude::*;
+
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::tracer::material;
+use crate::tracer::types;
+use crate::tracer::traceable;
+
+use super::Triangle;
+
+#[derive(Clone, Copy)]
+pub struct MeshFace {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+}
+
+pub struct MeshGroup {
+    pub name: String,
+    pub faces: Range<usize>,
+}
+
+pub struct TriangleMesh {
+    pub positions: Vec<Vector3<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+    pub uvs: Vec<Vector2<f32>>,
+    pub faces: Vec<MeshFace>,
+    pub groups: Vec<MeshGroup>,
+    pub material: material::Material,
+    bvh: traceable::Bvh,
+    // Running sum of face areas, only built once the mesh is sampled
+    area_cdf: OnceLock<Vec<f32>>,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Vector3<f32>>,
+        normals: Vec<Vector3<f32>>,
+        uvs: Vec<Vector2<f32>>,
+        faces: Vec<MeshFace>,
+        groups: Vec<MeshGroup>,
+        material: material::Material,
+    ) -> Self {
+        let face_bounds: Vec<traceable::Aabb> = faces
+            .iter()
+            .map(|face| {
+                traceable::Aabb::from_points(
+                    face.positions.iter().map(|&idx| positions[idx]),
+                )
+            })
+            .collect();
+        let bvh = traceable::Bvh::build(&face_bounds);
+
+        Self {
+            positions,
+            normals,
+            uvs,
+            faces,
+            groups,
+            material,
+            bvh,
+            area_cdf: OnceLock::new(),
+        }
+    }
+
+    // The faces of the groups called `name`, None when there are none
+    pub fn group(self, name: &str) -> Option<Self> {
+        let faces: Vec<MeshFace> = self
+            .groups
+            .iter()
+            .filter(|group| group.name == name)
+            .flat_map(|group| self.faces[group.faces.clone()].iter().copied())
+            .collect();
+        if faces.is_empty() {
+            return None;
+        }
+        let groups = vec![MeshGroup {
+            name: String::from(name),
+            faces: 0..faces.len(),
+        }];
+        Some(Self::new(self.positions, self.normals, self.uvs, faces, groups, self.material))
+    }
+
+    pub fn face_vertices(&self, face: &MeshFace) -> [Vector3<f32>; 3] {
+        [
+            self.positions[face.positions[0]],
+            self.positions[face.positions[1]],
+            self.positions[face.positions[2]],
+        ]
+    }
+
+    fn area_cdf(&self) -> &[f32] {
+        self.area_cdf.get_or_init(|| {
+            let mut total = 0.0;
+            self.faces
+                .iter()
+                .map(|face| {
+                    let [v0, v1, v2] = self.face_vertices(face);
+                    total += 0.5 * (v1 - v0).cross(v2 - v0).magnitude();
+                    total
+                })
+                .collect()
+        })
+    }
+
+    fn face_hit(&self, face: &MeshFace, ray: &types::Ray, t: f32, barycentric: Vector2<f32>) -> types::RayHit {
+        let [v0, v1, v2] = self.face_vertices(face);
+        let geometric_normal = (v1 - v0).cross(v2 - v0).normalize();
+        let w = 1.0 - barycentric.x - barycentric.y;
+
+        let normal = match face.normals {
+            Some([n0, n1, n2]) => {
+                let smooth = (self.normals[n0] * w
+                    + self.normals[n1] * barycentric.x
+                    + self.normals[n2] * barycentric.y)
+                    .normalize();
+                // Keep the shading normal on the same side as the winding so
+                // refraction can still tell entering from exiting rays.
+                if smooth.dot(geometric_normal) < 0.0 { -smooth } else { smooth }
+            }
+            None => geometric_normal,
+        };
+        let uv = match face.uvs {
+            Some([t0, t1, t2]) => self.uvs[t0] * w + self.uvs[t1] * barycentric.x + self.uvs[t2] * barycentric.y,
+            None => barycentric,
+        };
+        let (tangent, bitangent) = match face.uvs {
+            Some([t0, t1, t2]) => uv_tangents([v0, v1, v2], [self.uvs[t0], self.uvs[t1], self.uvs[t2]]),
+            None => None,
+        }
+        // Without UVs the barycentrics stand in for them
+        .unwrap_or((v1 - v0, v2 - v0));
+
+        types::RayHit {
+            distance: t,
+            hit: ray.origin + (ray.direction * t),
+            normal,
+            barycentric,
+            uv,
+            tangent,
+            bitangent,
+            material: self.material.clone(),
+        }
+    }
+}
+
+// Solves for the directions in which u and v grow across a triangle, None when
+// its UVs are degenerate
+fn uv_tangents(p: [Vector3<f32>; 3], uv: [Vector2<f32>; 3]) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let (e1, e2) = (p[1] - p[0], p[2] - p[0]);
+    let (d1, d2) = (uv[1] - uv[0], uv[2] - uv[0]);
+    let det = d1.x * d2.y - d2.x * d1.y;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let tangent = (e1 * d2.y - e2 * d1.y) / det;
+    let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+    Some((tangent, bitangent))
+}
+
+impl traceable::Traceable for TriangleMesh {
+    fn ray_intersect(&self, ray: &types::Ray) -> std::option::Option<types::RayHit> {
+        let (t, (idx, barycentric)) = self.bvh.traverse(ray, |idx| {
+            let [v0, v1, v2] = self.face_vertices(&self.faces[idx]);
+            Triangle::intersect(v0, v1, v2, ray).map(|(t, barycentric)| (t, (idx, barycentric)))
+        })?;
+        Some(self.face_hit(&self.faces[idx], ray, t, barycentric))
+    }
+
+    fn bounding_box(&self) -> Option<traceable::Aabb> {
+        self.bvh.bounds()
+    }
+}
+
+impl traceable::Sampleable for TriangleMesh {
+    fn sample_surface(&self, u: (f32, f32)) -> traceable::SurfaceSample {
+        let cdf = self.area_cdf();
+        let total = cdf.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            return traceable::SurfaceSample {
+                point: Vector3::zero(),
+                normal: Vector3::unit_z(),
+                uv: Vector2::zero(),
+                pdf: 0.0,
+            };
+        }
+        // Faces are picked by area, the rest of `u.0` is stretched back over
+        // [0, 1) to place the point inside the face
+        let target = u.0 * total;
+        let idx = cdf.partition_point(|&area| area <= target).min(cdf.len() - 1);
+        let start = if idx > 0 { cdf[idx - 1] } else { 0.0 };
+        let face_area = cdf[idx] - start;
+        let reused = if face_area > 0.0 { ((target - start) / face_area).clamp(0.0, 1.0) } else { 0.0 };
+
+        let face = &self.faces[idx];
+        let [v0, v1, v2] = self.face_vertices(face);
+        let b = Triangle::sample_barycentric((reused, u.1));
+        let uv = match face.uvs {
+            Some([t0, t1, t2]) => self.uvs[t0] * (1.0 - b.x - b.y) + self.uvs[t1] * b.x + self.uvs[t2] * b.y,
+            None => b,
+        };
+        traceable::SurfaceSample {
+            point: v0 + (v1 - v0) * b.x + (v2 - v0) * b.y,
+            normal: (v1 - v0).cross(v2 - v0).normalize(),
+            uv,
+            pdf: 1.0 / total,
+        }
+    }
+}
@@ -2,8 +2,12 @@ mod sphere;
 mod plane;
 mod disk;
 mod cube;
+mod triangle;
+mod mesh;
 
 pub use sphere::Sphere;
 pub use plane::Plane;
 pub use disk::Disk;
 pub use cube::Cube;
+pub use triangle::Triangle;
+pub use mesh::{MeshFace, MeshGroup, TriangleMesh};
@@ -1,8 +1,9 @@
-use cgmath::Vector3;
+use cgmath::{ Vector2, Vector3 };
 use cgmath::dot;
 use cgmath::prelude::*;
 
 use crate::tracer::material;
+use crate::tracer::ops;
 use crate::tracer::types;
 use crate::tracer::traceable;
 
@@ -38,7 +39,17 @@ impl traceable::Traceable for Plane {
         rayhit.distance = t;
         rayhit.hit = ray.origin + (ray.direction * t);
         rayhit.normal = self.normal;
-        rayhit.material = self.material;
+        // One unit of UV per unit of distance, textures repeat across the plane
+        let (tangent, bitangent) = ops::orthonormal_basis(self.normal);
+        let local = rayhit.hit - self.position;
+        rayhit.uv = Vector2::new(local.dot(tangent), local.dot(bitangent));
+        rayhit.tangent = tangent;
+        rayhit.bitangent = bitangent;
+        rayhit.material = self.material.clone();
         return Some(rayhit);
     }
+
+    fn bounding_box(&self) -> Option<traceable::Aabb> {
+        None
+    }
 }
@@ -1,8 +1,11 @@
-use cgmath::Vector3;
+use cgmath::{ Vector2, Vector3 };
 use cgmath::dot;
 use cgmath::prelude::*;
 
+use std::f32::consts::PI;
+
 use crate::tracer::material;
+use crate::tracer::ops;
 use crate::tracer::types;
 use crate::tracer::traceable;
 
@@ -36,6 +39,14 @@ impl Sphere {
         Ok(())
     }
 }
+// Longitude around the Y axis, latitude from the south pole
+fn sphere_uv(normal: Vector3<f32>) -> Vector2<f32> {
+    Vector2::new(
+        0.5 + normal.x.atan2(normal.z) / (2.0 * PI),
+        0.5 + normal.y.clamp(-1.0, 1.0).asin() / PI,
+    )
+}
+
 impl traceable::Traceable for Sphere {
     fn ray_intersect(&self, ray: &types::Ray) -> Option<types::RayHit> {
         let orig2center_v3 = self.center - ray.origin;
@@ -58,9 +69,68 @@ impl traceable::Traceable for Sphere {
         rayhit.distance = t0;
         rayhit.hit = ray.origin + (ray.direction * t0);
         rayhit.normal = (rayhit.hit - self.center).normalize();
-        rayhit.material = self.material;
+        rayhit.uv = sphere_uv(rayhit.normal);
+        // Eastward and northward, degenerate at the poles
+        rayhit.tangent = Vector3::new(rayhit.normal.z, 0.0, -rayhit.normal.x);
+        rayhit.bitangent = rayhit.normal.cross(rayhit.tangent);
+        rayhit.material = self.material.clone();
 
         return Some(rayhit);
     }
+
+    fn bounding_box(&self) -> Option<traceable::Aabb> {
+        let r = Vector3::new(self.radius, self.radius, self.radius);
+        Some(traceable::Aabb::new(self.center - r, self.center + r))
+    }
 }
 
+impl traceable::Sampleable for Sphere {
+    fn sample_surface(&self, u: (f32, f32)) -> traceable::SurfaceSample {
+        let z = 1.0 - 2.0 * u.0;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.1;
+        let normal = Vector3::new(r * phi.cos(), r * phi.sin(), z);
+        traceable::SurfaceSample {
+            point: self.center + normal * self.radius,
+            normal,
+            uv: sphere_uv(normal),
+            pdf: 1.0 / (4.0 * PI * self.radius * self.radius),
+        }
+    }
+
+    // Picks directions evenly within the cone the sphere subtends from `at`,
+    // so every point lies on the visible cap
+    fn sample_surface_from(&self, at: Vector3<f32>, u: (f32, f32)) -> traceable::SurfaceSample {
+        let to_center = self.center - at;
+        let sin2_max = self.radius * self.radius / to_center.magnitude2();
+        // On or inside the sphere the cone covers everything, and just outside
+        // it is too wide for the formulas below to hold up in f32
+        if sin2_max >= 1.0 - 1e-4 {
+            return self.sample_surface(u);
+        }
+        let axis = to_center.normalize();
+        let cos_max = (1.0 - sin2_max).sqrt();
+        // 1 - cos_max, without losing small cones to rounding
+        let cone = sin2_max / (1.0 + cos_max);
+
+        let cos_theta = 1.0 - u.0 * cone;
+        let sin2_theta = (1.0 - cos_theta * cos_theta).max(0.0);
+        // Angle at the center between the point and the direction towards `at`
+        let cos_alpha = sin2_theta / sin2_max.sqrt() + cos_theta * (1.0 - sin2_theta / sin2_max).max(0.0).sqrt();
+        let sin_alpha = (1.0 - cos_alpha * cos_alpha).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.1;
+        let (tangent, bitangent) = ops::orthonormal_basis(axis);
+        let normal = -axis * cos_alpha + (tangent * phi.cos() + bitangent * phi.sin()) * sin_alpha;
+        let point = self.center + normal * self.radius;
+
+        // Solid angle density turned into area density
+        let to_at = at - point;
+        let cos_light = normal.dot(to_at.normalize());
+        traceable::SurfaceSample {
+            point,
+            normal,
+            uv: sphere_uv(normal),
+            pdf: (cos_light / (2.0 * PI * cone * to_at.magnitude2())).max(0.0),
+        }
+    }
+}
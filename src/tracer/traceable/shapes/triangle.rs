@@ -0,0 +1,99 @@
+use cgmath::{ Vector2, Vector3 };
+use cgmath::prelude::*;
+
+use crate::tracer::material;
+use crate::tracer::types;
+use crate::tracer::traceable;
+
+pub struct Triangle {
+    pub v0: Vector3<f32>,
+    pub v1: Vector3<f32>,
+    pub v2: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub material: material::Material,
+}
+
+impl Triangle {
+    pub fn new(v0: Vector3<f32>, v1: Vector3<f32>, v2: Vector3<f32>, material: material::Material) -> Self {
+        Self {
+            v0,
+            v1,
+            v2,
+            normal: (v1 - v0).cross(v2 - v0).normalize(),
+            material,
+        }
+    }
+
+    // Möller–Trumbore. Returns the distance along the ray and the barycentric
+    // weights (u, v) of v1 and v2; the weight of v0 is 1 - u - v.
+    pub fn intersect(v0: Vector3<f32>, v1: Vector3<f32>, v2: Vector3<f32>, ray: &types::Ray) -> Option<(f32, Vector2<f32>)> {
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+
+        let pvec = ray.direction.cross(edge2);
+        let det = edge1.dot(pvec);
+        if det.abs() < 1e-8 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = ray.origin - v0;
+        let u = tvec.dot(pvec) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let qvec = tvec.cross(edge1);
+        let v = ray.direction.dot(qvec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(qvec) * inv_det;
+        if t < 0.0 {
+            return None;
+        }
+
+        Some((t, Vector2::new(u, v)))
+    }
+
+    // Uniformly distributed barycentric weights (u, v) of v1 and v2
+    pub fn sample_barycentric(u: (f32, f32)) -> Vector2<f32> {
+        let s = u.0.sqrt();
+        Vector2::new(s * (1.0 - u.1), s * u.1)
+    }
+}
+
+impl traceable::Traceable for Triangle {
+    fn ray_intersect(&self, ray: &types::Ray) -> std::option::Option<types::RayHit> {
+        let (t, barycentric) = Self::intersect(self.v0, self.v1, self.v2, ray)?;
+
+        let mut rayhit = types::RayHit::default();
+        rayhit.distance = t;
+        rayhit.hit = ray.origin + (ray.direction * t);
+        rayhit.normal = self.normal;
+        rayhit.barycentric = barycentric;
+        rayhit.uv = barycentric;
+        rayhit.tangent = self.v1 - self.v0;
+        rayhit.bitangent = self.v2 - self.v0;
+        rayhit.material = self.material.clone();
+        return Some(rayhit);
+    }
+
+    fn bounding_box(&self) -> Option<traceable::Aabb> {
+        Some(traceable::Aabb::from_points([self.v0, self.v1, self.v2]))
+    }
+}
+
+impl traceable::Sampleable for Triangle {
+    fn sample_surface(&self, u: (f32, f32)) -> traceable::SurfaceSample {
+        let b = Self::sample_barycentric(u);
+        let edges = (self.v1 - self.v0).cross(self.v2 - self.v0);
+        traceable::SurfaceSample {
+            point: self.v0 + (self.v1 - self.v0) * b.x + (self.v2 - self.v0) * b.y,
+            normal: self.normal,
+            uv: b,
+            pdf: 2.0 / edges.magnitude(),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use cgmath::{ Matrix4, Vector3, Vector4 };
+use cgmath::prelude::*;
+
+use std::sync::Arc;
+
+use crate::tracer::types;
+use crate::tracer::traceable;
+
+use super::{ Aabb, Traceable };
+
+// Places any traceable in the scene through an affine transform. The wrapped
+// object is shared, so one mesh can be instanced many times.
+pub struct Transformed {
+    object: Arc<dyn Traceable>,
+    transform: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+}
+
+impl Transformed {
+    pub fn new<T: Into<Arc<dyn Traceable>>>(object: T, transform: Matrix4<f32>) -> Result<Self, String> {
+        let inverse = transform
+            .invert()
+            .ok_or_else(|| String::from("Transform is not invertible"))?;
+        Ok(Self {
+            object: object.into(),
+            transform,
+            inverse,
+        })
+    }
+}
+
+fn transform_point(m: &Matrix4<f32>, p: Vector3<f32>) -> Vector3<f32> {
+    (m * Vector4::new(p.x, p.y, p.z, 1.0)).truncate()
+}
+
+fn transform_vector(m: &Matrix4<f32>, v: Vector3<f32>) -> Vector3<f32> {
+    (m * Vector4::new(v.x, v.y, v.z, 0.0)).truncate()
+}
+
+impl traceable::Traceable for Transformed {
+    fn ray_intersect(&self, ray: &types::Ray) -> std::option::Option<types::RayHit> {
+        let local_dir = transform_vector(&self.inverse, ray.direction);
+        let scale = local_dir.magnitude();
+        if scale <= 0.0 {
+            return None;
+        }
+        let local_ray = types::Ray::new(
+            transform_point(&self.inverse, ray.origin),
+            local_dir / scale,
+        ).ok()?;
+
+        let mut rayhit = self.object.ray_intersect(&local_ray)?;
+        // Shapes report distances along a unit direction, so undo the
+        // normalization to get the distance along the world ray
+        rayhit.distance /= scale;
+        rayhit.hit = ray.origin + ray.direction * rayhit.distance;
+        // Normals transform by the inverse transpose
+        rayhit.normal = transform_vector(&self.inverse.transpose(), rayhit.normal).normalize();
+        // Tangents lie in the surface and follow it like any other direction
+        rayhit.tangent = transform_vector(&self.transform, rayhit.tangent);
+        rayhit.bitangent = transform_vector(&self.transform, rayhit.bitangent);
+        Some(rayhit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let local = self.object.bounding_box()?;
+        Some(Aabb::from_points(
+            local.corners().iter().map(|&corner| transform_point(&self.transform, corner)),
+        ))
+    }
+}